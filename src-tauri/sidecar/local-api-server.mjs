@@ -98,14 +98,10 @@ globalThis.fetch = async function ipv4Fetch(input, init) {
   });
 };
 
-const ALLOWED_ENV_KEYS = new Set([
-  'GROQ_API_KEY', 'OPENROUTER_API_KEY', 'FRED_API_KEY', 'EIA_API_KEY',
-  'CLOUDFLARE_API_TOKEN', 'ACLED_ACCESS_TOKEN', 'URLHAUS_AUTH_KEY',
-  'OTX_API_KEY', 'ABUSEIPDB_API_KEY', 'WINGBITS_API_KEY', 'WS_RELAY_URL',
-  'VITE_OPENSKY_RELAY_URL', 'OPENSKY_CLIENT_ID', 'OPENSKY_CLIENT_SECRET',
-  'AISSTREAM_API_KEY', 'VITE_WS_RELAY_URL', 'FINNHUB_API_KEY', 'NASA_FIRMS_API_KEY',
-  'OLLAMA_API_URL', 'OLLAMA_MODEL', 'WORLDMONITOR_API_KEY', 'WTO_API_KEY',
-]);
+// Keys the desktop vault may set; the Rust side loads the same registry.
+const ALLOWED_ENV_KEYS = new Set(
+  JSON.parse(readFileSync(new URL('./secret-keys.json', import.meta.url), 'utf8')).keys.map((entry) => entry.key),
+);
 
 const CHROME_UA = 'Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36';
 
@@ -462,6 +458,7 @@ function resolveConfig(options = {}) {
   const mode = String(options.mode ?? process.env.LOCAL_API_MODE ?? 'desktop-sidecar');
   const cloudFallback = String(options.cloudFallback ?? process.env.LOCAL_API_CLOUD_FALLBACK ?? '') === 'true';
   const logger = options.logger ?? console;
+  const onShutdown = typeof options.onShutdown === 'function' ? options.onShutdown : null;
 
   return {
     port,
@@ -471,6 +468,8 @@ function resolveConfig(options = {}) {
     mode,
     cloudFallback,
     logger,
+    onShutdown,
+    shutdownRequested: false,
   };
 }
 
@@ -944,6 +943,19 @@ async function dispatch(requestUrl, req, routes, context) {
       routes: routes.length,
     });
   }
+  // Graceful shutdown requested by the desktop shell. The response is sent
+  // first; the server then stops accepting connections and lets in-flight
+  // requests finish before the process exits.
+  if (requestUrl.pathname === '/api/local-shutdown') {
+    if (req.method !== 'POST') {
+      return json({ error: 'POST required' }, 405);
+    }
+    if (!context.onShutdown) {
+      return json({ error: 'Shutdown not supported in this mode' }, 501);
+    }
+    context.shutdownRequested = true;
+    return json({ ok: true });
+  }
   if (requestUrl.pathname === '/api/local-traffic-log') {
     if (req.method === 'DELETE') {
       trafficLog.length = 0;
@@ -1169,7 +1181,8 @@ export async function createLocalApiServer(options = {}) {
       || requestUrl.pathname === '/api/local-traffic-log'
       || requestUrl.pathname === '/api/local-debug-toggle'
       || requestUrl.pathname === '/api/local-env-update'
-      || requestUrl.pathname === '/api/local-validate-secret';
+      || requestUrl.pathname === '/api/local-validate-secret'
+      || requestUrl.pathname === '/api/local-shutdown';
 
     try {
       const response = await dispatch(requestUrl, req, routes, context);
@@ -1197,6 +1210,11 @@ export async function createLocalApiServer(options = {}) {
         delete headers['content-length'];
       }
 
+      if (context.shutdownRequested) {
+        context.shutdownRequested = false;
+        res.once('finish', () => context.onShutdown('shutdown request'));
+      }
+
       res.writeHead(response.status, headers);
       res.end(body);
     } catch (error) {
@@ -1252,9 +1270,33 @@ export async function createLocalApiServer(options = {}) {
   };
 }
 
+const SHUTDOWN_GRACE_MS = 2000;
+
+async function shutdownAndExit(app, reason) {
+  console.log(`[local-api] shutting down (${reason})`);
+  // Hard deadline in case a handler never completes; the desktop shell
+  // force-kills shortly after this anyway.
+  setTimeout(() => process.exit(0), SHUTDOWN_GRACE_MS).unref();
+  try {
+    app.server.closeIdleConnections?.();
+    await app.close();
+  } catch (error) {
+    console.error('[local-api] shutdown error', error);
+  }
+  process.exit(0);
+}
+
 if (isMainModule()) {
   try {
-    const app = await createLocalApiServer();
+    let shuttingDown = false;
+    const app = await createLocalApiServer({
+      onShutdown: (reason) => {
+        if (shuttingDown) return;
+        shuttingDown = true;
+        void shutdownAndExit(app, reason);
+      },
+    });
+    process.once('SIGTERM', () => app.context.onShutdown('SIGTERM'));
     await app.start();
   } catch (error) {
     console.error('[local-api] startup failed', error);
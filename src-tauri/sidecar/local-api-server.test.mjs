@@ -1112,6 +1112,49 @@ test('rejects unauthenticated requests to /api/local-status when token is set',
   }
 });
 
+test('local-shutdown requires auth and invokes onShutdown after responding', async () => {
+  const localApi = await setupApiDir({});
+  const originalToken = process.env.LOCAL_API_TOKEN;
+  process.env.LOCAL_API_TOKEN = 'security-test-token';
+
+  const shutdownReasons = [];
+  const app = await createLocalApiServer({
+    port: 0,
+    apiDir: localApi.apiDir,
+    logger: { log() {}, warn() {}, error() {} },
+    onShutdown: (reason) => shutdownReasons.push(reason),
+  });
+  const { port } = await app.start();
+
+  try {
+    const unauthed = await fetch(`http://127.0.0.1:${port}/api/local-shutdown`, { method: 'POST' });
+    assert.equal(unauthed.status, 401);
+    assert.deepEqual(shutdownReasons, []);
+
+    const wrongMethod = await fetch(`http://127.0.0.1:${port}/api/local-shutdown`, {
+      headers: { 'Authorization': 'Bearer security-test-token' },
+    });
+    assert.equal(wrongMethod.status, 405);
+
+    const authed = await fetch(`http://127.0.0.1:${port}/api/local-shutdown`, {
+      method: 'POST',
+      headers: { 'Authorization': 'Bearer security-test-token' },
+    });
+    assert.equal(authed.status, 200);
+    assert.equal((await authed.json()).ok, true);
+    await new Promise((resolve) => setImmediate(resolve));
+    assert.deepEqual(shutdownReasons, ['shutdown request']);
+  } finally {
+    if (originalToken !== undefined) {
+      process.env.LOCAL_API_TOKEN = originalToken;
+    } else {
+      delete process.env.LOCAL_API_TOKEN;
+    }
+    await app.close();
+    await localApi.cleanup();
+  }
+});
+
 test('rejects unauthenticated requests to /api/local-traffic-log when token is set', async () => {
   const localApi = await setupApiDir({});
   const originalToken = process.env.LOCAL_API_TOKEN;
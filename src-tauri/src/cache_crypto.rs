@@ -0,0 +1,121 @@
+//! At-rest encryption for persistent cache values.
+//!
+//! Each value is sealed with XChaCha20-Poly1305 under a random 256-bit data
+//! key held in the OS keychain. The cache key is bound as associated data, so
+//! a row moved to a different key fails authentication instead of decrypting
+//! as the wrong panel.
+//!
+//! Sealed layout: `MAGIC (4) || nonce (24) || ciphertext+tag`.
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+/// Leading bytes of every sealed value.
+pub(crate) const MAGIC: &[u8; 4] = b"WMC1";
+const NONCE_LEN: usize = 24;
+pub(crate) const KEY_LEN: usize = 32;
+
+#[derive(Clone)]
+pub(crate) struct CacheCipher {
+    aead: XChaCha20Poly1305,
+}
+
+impl CacheCipher {
+    pub(crate) fn new(key: &[u8; KEY_LEN]) -> Self {
+        CacheCipher {
+            aead: XChaCha20Poly1305::new(key.into()),
+        }
+    }
+
+    pub(crate) fn generate_key() -> [u8; KEY_LEN] {
+        let mut key = [0u8; KEY_LEN];
+        getrandom::getrandom(&mut key).expect("OS CSPRNG unavailable");
+        key
+    }
+
+    pub(crate) fn seal(&self, cache_key: &str, plaintext: &[u8]) -> Vec<u8> {
+        let mut nonce = [0u8; NONCE_LEN];
+        getrandom::getrandom(&mut nonce).expect("OS CSPRNG unavailable");
+        let ciphertext = self
+            .aead
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: cache_key.as_bytes(),
+                },
+            )
+            .expect("XChaCha20-Poly1305 encryption cannot fail for in-memory buffers");
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+
+    pub(crate) fn open(&self, cache_key: &str, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        if !is_sealed(sealed) || sealed.len() < MAGIC.len() + NONCE_LEN {
+            return Err("not an encrypted cache value".to_string());
+        }
+        let (nonce, ciphertext) = sealed[MAGIC.len()..].split_at(NONCE_LEN);
+        self.aead
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: cache_key.as_bytes(),
+                },
+            )
+            .map_err(|_| "cache value failed authentication".to_string())
+    }
+}
+
+pub(crate) fn is_sealed(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Hex form stored in the keychain, matching how the sidecar token is encoded.
+pub(crate) fn encode_key(key: &[u8; KEY_LEN]) -> String {
+    key.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub(crate) fn decode_key(encoded: &str) -> Result<[u8; KEY_LEN], String> {
+    let encoded = encoded.trim();
+    if encoded.len() != KEY_LEN * 2 || !encoded.is_ascii() {
+        return Err("cache encryption key has the wrong length".to_string());
+    }
+    let mut key = [0u8; KEY_LEN];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&encoded[i * 2..i * 2 + 2], 16)
+            .map_err(|_| "cache encryption key is not valid hex".to_string())?;
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_round_trips_and_binds_the_cache_key() {
+        let cipher = CacheCipher::new(&CacheCipher::generate_key());
+        let sealed = cipher.seal("feed:world", b"{\"updatedAt\":1}");
+        assert!(is_sealed(&sealed));
+        assert_eq!(
+            cipher.open("feed:world", &sealed).unwrap(),
+            b"{\"updatedAt\":1}"
+        );
+        assert!(cipher.open("feed:other", &sealed).is_err());
+
+        let other = CacheCipher::new(&CacheCipher::generate_key());
+        assert!(other.open("feed:world", &sealed).is_err());
+    }
+
+    #[test]
+    fn key_encoding_round_trips() {
+        let key = CacheCipher::generate_key();
+        assert_eq!(decode_key(&encode_key(&key)).unwrap(), key);
+        assert!(decode_key("abcd").is_err());
+        assert!(decode_key(&"zz".repeat(KEY_LEN)).is_err());
+    }
+}
@@ -0,0 +1,282 @@
+//! Admission checks for cache writes coming from the webview.
+//!
+//! A write is refused before it is parsed or stored when its key is
+//! malformed or outside the whitelist, its JSON exceeds the size limit for
+//! the key's prefix, or it would take a capped prefix over its total byte
+//! budget. Refusals are returned to the frontend as
+//! [`CacheWriteRefusal`] objects rather than bare strings so it can tell a
+//! policy refusal from a transport failure.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Keys of `api-response:` entries embed the request URL, so this is
+/// generous; anything longer is a bug.
+const MAX_KEY_LEN: usize = 1024;
+const DEFAULT_MAX_ENTRY_BYTES: u64 = 8 * 1024 * 1024;
+/// Prefixes the frontend writes (`utils/proxy.ts`, `services/rss.ts`,
+/// `utils/circuit-breaker.ts`, `services/cached-risk-scores.ts`,
+/// `components/InsightsPanel.ts`, `app/data-loader.ts`), which are the only
+/// ones the webview may write, with the default TTL of each. Entries under a
+/// prefix without a TTL never expire and are only subject to the byte quota.
+pub(crate) const FRONTEND_PREFIXES: &[(&str, Option<Duration>)] = &[
+    ("api-response:", Some(Duration::from_secs(3 * 24 * 60 * 60))),
+    ("feed:", Some(Duration::from_secs(7 * 24 * 60 * 60))),
+    ("breaker:", Some(Duration::from_secs(7 * 24 * 60 * 60))),
+    ("risk-scores:", None),
+    ("summary:", None),
+    ("happy-all-items", None),
+];
+/// Per-prefix overrides of the per-write limit; the longest prefix wins.
+const DEFAULT_ENTRY_LIMITS: &[(&str, u64)] = &[
+    ("api-response:", 4 * 1024 * 1024),
+    ("summary:", 1024 * 1024),
+];
+/// Caps on the summed size of every stored entry under a prefix, so one
+/// busy prefix cannot push everything else out of the shared quota.
+const DEFAULT_PREFIX_LIMITS: &[(&str, u64)] = &[
+    ("api-response:", 32 * 1024 * 1024),
+    ("feed:", 16 * 1024 * 1024),
+    ("summary:", 4 * 1024 * 1024),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum RefusalCode {
+    InvalidKey,
+    KeyNotAllowed,
+    TooLarge,
+    /// The write would take a capped prefix over its total byte limit.
+    PrefixFull,
+    InvalidJson,
+}
+
+impl RefusalCode {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            RefusalCode::InvalidKey => "invalidKey",
+            RefusalCode::KeyNotAllowed => "keyNotAllowed",
+            RefusalCode::TooLarge => "tooLarge",
+            RefusalCode::PrefixFull => "prefixFull",
+            RefusalCode::InvalidJson => "invalidJson",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CacheWriteRefusal {
+    pub code: RefusalCode,
+    pub key: String,
+    pub message: String,
+    /// Size of the refused payload in bytes, for `tooLarge`; for
+    /// `prefixFull`, what the prefix would hold after the write.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Limit that applied, for `tooLarge` and `prefixFull`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+impl CacheWriteRefusal {
+    pub(crate) fn new(code: RefusalCode, key: &str, message: String) -> Self {
+        CacheWriteRefusal {
+            code,
+            key: key.to_string(),
+            message,
+            size: None,
+            limit: None,
+        }
+    }
+}
+
+/// Error returned by the cache write commands. `refusals` is empty when the
+/// write failed for another reason (untrusted window, unknown namespace).
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CacheWriteError {
+    pub message: String,
+    pub refusals: Vec<CacheWriteRefusal>,
+}
+
+impl From<String> for CacheWriteError {
+    fn from(message: String) -> Self {
+        CacheWriteError {
+            message,
+            refusals: Vec::new(),
+        }
+    }
+}
+
+impl From<Vec<CacheWriteRefusal>> for CacheWriteError {
+    fn from(refusals: Vec<CacheWriteRefusal>) -> Self {
+        let message = match refusals.as_slice() {
+            [only] => only.message.clone(),
+            _ => format!("{} cache writes refused", refusals.len()),
+        };
+        CacheWriteError { message, refusals }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct WriteLimits {
+    pub max_entry_bytes: u64,
+    /// `(key prefix, max bytes)`; the longest matching prefix wins over
+    /// `max_entry_bytes`.
+    pub entry_limits: Vec<(String, u64)>,
+    /// `(key prefix, max bytes)` for the summed size of all entries under
+    /// the prefix. Every matching prefix applies.
+    pub prefix_limits: Vec<(String, u64)>,
+    /// A key must start with one of these.
+    pub allowed_prefixes: Vec<String>,
+}
+
+impl Default for WriteLimits {
+    fn default() -> Self {
+        WriteLimits {
+            max_entry_bytes: DEFAULT_MAX_ENTRY_BYTES,
+            entry_limits: DEFAULT_ENTRY_LIMITS
+                .iter()
+                .map(|(prefix, limit)| ((*prefix).to_string(), *limit))
+                .collect(),
+            prefix_limits: DEFAULT_PREFIX_LIMITS
+                .iter()
+                .map(|(prefix, limit)| ((*prefix).to_string(), *limit))
+                .collect(),
+            allowed_prefixes: FRONTEND_PREFIXES
+                .iter()
+                .map(|(prefix, _)| (*prefix).to_string())
+                .collect(),
+        }
+    }
+}
+
+impl WriteLimits {
+    fn limit_for(&self, key: &str) -> u64 {
+        self.entry_limits
+            .iter()
+            .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.max_entry_bytes, |(_, limit)| *limit)
+    }
+
+    /// Check a write of `json_len` bytes of JSON to `key`.
+    pub(crate) fn check(&self, key: &str, json_len: usize) -> Result<(), CacheWriteRefusal> {
+        if key.is_empty() || key.len() > MAX_KEY_LEN || key.chars().any(char::is_control) {
+            return Err(CacheWriteRefusal::new(
+                RefusalCode::InvalidKey,
+                key,
+                format!("Cache key must be 1-{MAX_KEY_LEN} bytes without control characters"),
+            ));
+        }
+        if !self
+            .allowed_prefixes
+            .iter()
+            .any(|prefix| key.starts_with(prefix.as_str()))
+        {
+            return Err(CacheWriteRefusal::new(
+                RefusalCode::KeyNotAllowed,
+                key,
+                format!("Cache key '{key}' does not match an allowed prefix"),
+            ));
+        }
+        let size = json_len as u64;
+        let limit = self.limit_for(key);
+        if size > limit {
+            return Err(CacheWriteRefusal {
+                size: Some(size),
+                limit: Some(limit),
+                ..CacheWriteRefusal::new(
+                    RefusalCode::TooLarge,
+                    key,
+                    format!("Cache entry '{key}' is {size} bytes, over the {limit} byte limit"),
+                )
+            });
+        }
+        Ok(())
+    }
+
+    /// Check that storing `json_len` bytes at `key` keeps every capped
+    /// prefix covering it within budget. `usage` returns the bytes already
+    /// held under a prefix, not counting the current value of `key`.
+    pub(crate) fn check_total(
+        &self,
+        key: &str,
+        json_len: usize,
+        usage: impl Fn(&str) -> u64,
+    ) -> Result<(), CacheWriteRefusal> {
+        for (prefix, limit) in &self.prefix_limits {
+            if !key.starts_with(prefix.as_str()) {
+                continue;
+            }
+            let total = usage(prefix) + json_len as u64;
+            if total > *limit {
+                return Err(CacheWriteRefusal {
+                    size: Some(total),
+                    limit: Some(*limit),
+                    ..CacheWriteRefusal::new(
+                        RefusalCode::PrefixFull,
+                        key,
+                        format!(
+                            "Cache entries under '{prefix}' would take {total} bytes, over the {limit} byte limit"
+                        ),
+                    )
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_key_format_whitelist_and_prefix_limits() {
+        let limits = WriteLimits {
+            max_entry_bytes: 100,
+            entry_limits: vec![("feed:".into(), 50), ("feed:big".into(), 500)],
+            prefix_limits: Vec::new(),
+            allowed_prefixes: vec!["feed:".into(), "summary:".into()],
+        };
+        assert!(limits.check("summary:brief", 100).is_ok());
+        assert_eq!(
+            limits.check("summary:brief", 101).unwrap_err().limit,
+            Some(100)
+        );
+        assert_eq!(
+            limits.check("feed:world", 51).unwrap_err().code,
+            RefusalCode::TooLarge
+        );
+        assert!(limits.check("feed:big:world", 400).is_ok());
+        assert_eq!(
+            limits.check("scratch:x", 1).unwrap_err().code,
+            RefusalCode::KeyNotAllowed
+        );
+        assert_eq!(
+            limits.check("feed:a\nb", 1).unwrap_err().code,
+            RefusalCode::InvalidKey
+        );
+        assert_eq!(
+            limits.check("", 1).unwrap_err().code,
+            RefusalCode::InvalidKey
+        );
+    }
+
+    #[test]
+    fn prefix_caps_count_stored_usage() {
+        let limits = WriteLimits {
+            prefix_limits: vec![("feed:".into(), 100), ("feed:big".into(), 500)],
+            ..WriteLimits::default()
+        };
+        assert!(limits.check_total("feed:a", 40, |_| 60).is_ok());
+        let refusal = limits.check_total("feed:a", 41, |_| 60).unwrap_err();
+        assert_eq!(refusal.code, RefusalCode::PrefixFull);
+        assert_eq!((refusal.size, refusal.limit), (Some(101), Some(100)));
+        // Both caps apply to `feed:big:` keys.
+        assert!(limits.check_total("feed:big:a", 200, |_| 0).is_err());
+        assert!(limits.check_total("summary:a", 1000, |_| 1000).is_ok());
+    }
+}
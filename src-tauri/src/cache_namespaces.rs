@@ -0,0 +1,278 @@
+//! One persistent cache store per app variant.
+//!
+//! The World, Tech and Finance builds normally get separate app data dirs
+//! from their bundle identifiers, but a build that switches variant at
+//! runtime (or a developer running several variants from one build) shares
+//! a single dir. Each variant therefore gets its own database under
+//! `cache/<namespace>/`, opened on first use. `cache-policy.json` stays in
+//! the app data dir and applies to every namespace.
+//!
+//! The store from before namespacing lived directly in the app data dir; it
+//! is moved into the build's default namespace the first time that opens.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::cache_crypto::CacheCipher;
+use crate::persistent_cache::{
+    self, CacheLogger, CachePolicy, ImportMode, ImportReport, PersistentCache,
+};
+
+const NAMESPACES_DIR: &str = "cache";
+const MAX_NAMESPACE_LEN: usize = 32;
+
+type SharedLogger = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+/// Called once for each store as it is opened, before any command sees it.
+pub(crate) type StoreOpened = Box<dyn Fn(&str, &Arc<PersistentCache>) + Send + Sync>;
+
+pub(crate) struct CacheNamespaces {
+    /// App data dir; `None` keeps every namespace in memory.
+    root: Option<PathBuf>,
+    /// Used when a command names no namespace, and adopts the
+    /// pre-namespacing store.
+    default_namespace: String,
+    policy: CachePolicy,
+    cipher: Option<CacheCipher>,
+    log: SharedLogger,
+    stores: Mutex<HashMap<String, Arc<PersistentCache>>>,
+    on_open: OnceLock<StoreOpened>,
+}
+
+/// Namespaces become directory names, so only short lowercase slugs pass.
+pub(crate) fn validate_namespace(namespace: &str) -> Result<(), String> {
+    let valid = !namespace.is_empty()
+        && namespace.len() <= MAX_NAMESPACE_LEN
+        && namespace
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid cache namespace '{namespace}'"))
+    }
+}
+
+/// Variant a bundle identifier was built for (see the `tauri*.conf.json`
+/// files); anything unrecognised is the full World Monitor build.
+pub(crate) fn namespace_for_identifier(identifier: &str) -> &'static str {
+    if identifier.contains(".tech.") {
+        "tech"
+    } else if identifier.contains(".finance.") {
+        "finance"
+    } else {
+        "full"
+    }
+}
+
+impl CacheNamespaces {
+    pub(crate) fn new(
+        root: Option<PathBuf>,
+        default_namespace: &str,
+        cipher: Option<CacheCipher>,
+        log: impl Fn(&str, &str) + Send + Sync + 'static,
+    ) -> Self {
+        let log: SharedLogger = Arc::new(log);
+        let policy = match root.as_deref() {
+            Some(root) => {
+                let sink = log.clone();
+                CachePolicy::load(
+                    root,
+                    &(Box::new(move |level: &str, message: &str| sink(level, message))
+                        as CacheLogger),
+                )
+            }
+            None => CachePolicy::default(),
+        };
+        CacheNamespaces {
+            root,
+            default_namespace: default_namespace.to_string(),
+            policy,
+            cipher,
+            log,
+            stores: Mutex::new(HashMap::new()),
+            on_open: OnceLock::new(),
+        }
+    }
+
+    /// Install the open hook. Only the first call takes effect, and stores
+    /// opened before it was set never see it.
+    pub(crate) fn set_open_hook(&self, hook: StoreOpened) {
+        let _ = self.on_open.set(hook);
+    }
+
+    /// Namespace used when a command passes none.
+    pub(crate) fn default_namespace(&self) -> &str {
+        &self.default_namespace
+    }
+
+    /// The store for `namespace` (or the default one), opening it on first
+    /// use.
+    pub(crate) fn get(&self, namespace: Option<&str>) -> Result<Arc<PersistentCache>, String> {
+        let namespace = namespace.unwrap_or(&self.default_namespace);
+        validate_namespace(namespace)?;
+        let mut stores = self.stores.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(store) = stores.get(namespace) {
+            return Ok(store.clone());
+        }
+        let store = Arc::new(self.open_store(namespace));
+        if let Some(hook) = self.on_open.get() {
+            hook(namespace, &store);
+        }
+        stores.insert(namespace.to_string(), store.clone());
+        Ok(store)
+    }
+
+    /// Every store opened so far, for background flush and eviction passes.
+    pub(crate) fn open_stores(&self) -> Vec<(String, Arc<PersistentCache>)> {
+        let stores = self.stores.lock().unwrap_or_else(|e| e.into_inner());
+        stores
+            .iter()
+            .map(|(namespace, store)| (namespace.clone(), store.clone()))
+            .collect()
+    }
+
+    /// Copy entries whose key starts with `prefix` from one namespace to
+    /// another, keeping target entries that are newer and skipping those the
+    /// target's write limits refuse. History is not copied.
+    pub(crate) fn copy(&self, from: &str, to: &str, prefix: &str) -> Result<ImportReport, String> {
+        if from == to {
+            return Err("Source and target cache namespaces are the same".to_string());
+        }
+        let entries = self.get(Some(from))?.snapshot_prefix(prefix);
+        Ok(self.get(Some(to))?.import(entries, ImportMode::Merge))
+    }
+
+    fn open_store(&self, namespace: &str) -> PersistentCache {
+        let Some(root) = self.root.as_deref() else {
+            return PersistentCache::in_memory();
+        };
+        let dir = namespace_dir(root, namespace);
+        if namespace == self.default_namespace {
+            match persistent_cache::relocate_store(root, &dir) {
+                Ok(0) => {}
+                Ok(moved) => (self.log)(
+                    "INFO",
+                    &format!("cache: moved {moved} files into namespace '{namespace}'"),
+                ),
+                Err(e) => (self.log)(
+                    "WARN",
+                    &format!("cache: failed to move store into namespace '{namespace}': {e}"),
+                ),
+            }
+        }
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            (self.log)(
+                "ERROR",
+                &format!("cache namespace '{namespace}' unavailable, caching in memory only: {e}"),
+            );
+            return PersistentCache::in_memory();
+        }
+        let sink = self.log.clone();
+        let prefix = format!("[{namespace}] ");
+        PersistentCache::open_with_policy(
+            &dir,
+            self.policy.clone(),
+            self.cipher.clone(),
+            Box::new(move |level, message| sink(level, &format!("{prefix}{message}"))),
+        )
+    }
+}
+
+/// Whether any store under `root`, namespaced or not, holds encrypted
+/// values.
+pub(crate) fn holds_sealed_entries(root: &Path) -> bool {
+    let namespaced = std::fs::read_dir(root.join(NAMESPACES_DIR))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path().join(persistent_cache::CACHE_DB_FILE));
+    std::iter::once(root.join(persistent_cache::CACHE_DB_FILE))
+        .chain(namespaced)
+        .any(|db_path| persistent_cache::holds_sealed_entries(&db_path))
+}
+
+fn namespace_dir(root: &Path, namespace: &str) -> PathBuf {
+    root.join(NAMESPACES_DIR).join(namespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn namespaces_are_isolated_and_copy_merges() {
+        let root = temp_dir("isolated");
+        let namespaces = CacheNamespaces::new(Some(root.clone()), "full", None, |_, _| {});
+        let full = namespaces.get(None).unwrap();
+        let tech = namespaces.get(Some("tech")).unwrap();
+        full.put(
+            "summary:countries".into(),
+            json!({"updatedAt": 2, "data": "new"}),
+        )
+        .unwrap();
+        full.put("feed:world".into(), json!({"updatedAt": 1}))
+            .unwrap();
+        tech.put("summary:borders".into(), json!({"updatedAt": 1}))
+            .unwrap();
+        assert!(tech.get("feed:world").is_none());
+        assert!(Arc::ptr_eq(&tech, &namespaces.get(Some("tech")).unwrap()));
+
+        let report = namespaces.copy("full", "tech", "summary:").unwrap();
+        assert_eq!(report.imported, 1);
+        assert_eq!(
+            tech.get("summary:countries"),
+            Some(json!({"updatedAt": 2, "data": "new"}))
+        );
+        assert!(tech.get("feed:world").is_none());
+        assert!(namespaces.copy("tech", "tech", "").is_err());
+        assert!(namespaces.get(Some("../full")).is_err());
+
+        drop((full, tech, namespaces));
+        assert!(root
+            .join("cache/full")
+            .join(persistent_cache::CACHE_DB_FILE)
+            .exists());
+        assert!(root
+            .join("cache/tech")
+            .join(persistent_cache::CACHE_DB_FILE)
+            .exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn default_namespace_adopts_the_unnamespaced_store() {
+        let root = temp_dir("adopt");
+        {
+            let legacy = PersistentCache::open(&root, None, Box::new(|_, _| {}));
+            legacy.put("kept".into(), json!({"updatedAt": 1})).unwrap();
+            legacy.flush().unwrap();
+        }
+        let namespaces = CacheNamespaces::new(Some(root.clone()), "tech", None, |_, _| {});
+        assert!(namespaces.get(Some("full")).unwrap().get("kept").is_none());
+        assert_eq!(
+            namespaces.get(None).unwrap().get("kept"),
+            Some(json!({"updatedAt": 1}))
+        );
+        assert!(!root.join(persistent_cache::CACHE_DB_FILE).exists());
+        drop(namespaces);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn identifiers_map_to_variants() {
+        assert_eq!(namespace_for_identifier("app.worldmonitor.desktop"), "full");
+        assert_eq!(
+            namespace_for_identifier("app.worldmonitor.tech.desktop"),
+            "tech"
+        );
+        assert_eq!(
+            namespace_for_identifier("app.worldmonitor.finance.desktop"),
+            "finance"
+        );
+    }
+}
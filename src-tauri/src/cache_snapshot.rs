@@ -0,0 +1,157 @@
+//! Portable export format for the persistent cache.
+//!
+//! A snapshot is gzip-compressed JSON: a [`SnapshotManifest`] plus the raw
+//! entries. Values are written decrypted so a snapshot can be imported on a
+//! machine with a different keychain; the file is as sensitive as the panels
+//! it contains.
+
+use std::io::{Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub(crate) const SNAPSHOT_FORMAT: &str = "world-monitor-cache";
+pub(crate) const SNAPSHOT_VERSION: u32 = 1;
+pub(crate) const SNAPSHOT_EXTENSION: &str = "json.gz";
+/// Decompression is capped so a crafted file cannot exhaust memory.
+const MAX_SNAPSHOT_BYTES: u64 = 512 * 1024 * 1024;
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SnapshotManifest {
+    pub format: String,
+    pub version: u32,
+    pub app_version: String,
+    pub variant: String,
+    pub key_count: usize,
+    pub created_at: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    manifest: SnapshotManifest,
+    entries: Map<String, Value>,
+}
+
+impl SnapshotManifest {
+    pub(crate) fn new(app_version: &str, variant: &str, key_count: usize, created_at: i64) -> Self {
+        SnapshotManifest {
+            format: SNAPSHOT_FORMAT.to_string(),
+            version: SNAPSHOT_VERSION,
+            app_version: app_version.to_string(),
+            variant: variant.to_string(),
+            key_count,
+            created_at,
+        }
+    }
+}
+
+pub(crate) fn encode_snapshot(
+    manifest: SnapshotManifest,
+    entries: Map<String, Value>,
+) -> Result<Vec<u8>, String> {
+    let snapshot = Snapshot { manifest, entries };
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    serde_json::to_writer(&mut encoder, &snapshot)
+        .map_err(|e| format!("Failed to serialize cache snapshot: {e}"))?;
+    encoder
+        .flush()
+        .and_then(|_| encoder.finish())
+        .map_err(|e| format!("Failed to compress cache snapshot: {e}"))
+}
+
+/// Decompress and fully validate a snapshot. Nothing is returned unless the
+/// whole file is well-formed, so callers can apply it without partial state.
+pub(crate) fn decode_snapshot(
+    bytes: &[u8],
+) -> Result<(SnapshotManifest, Map<String, Value>), String> {
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return Err("Not a World Monitor cache snapshot (expected a .json.gz file)".to_string());
+    }
+    let mut json = Vec::new();
+    GzDecoder::new(bytes)
+        .take(MAX_SNAPSHOT_BYTES + 1)
+        .read_to_end(&mut json)
+        .map_err(|e| format!("Cache snapshot is corrupt: {e}"))?;
+    if json.len() as u64 > MAX_SNAPSHOT_BYTES {
+        return Err(format!(
+            "Cache snapshot exceeds {} MB uncompressed",
+            MAX_SNAPSHOT_BYTES / (1024 * 1024)
+        ));
+    }
+
+    let snapshot: Snapshot =
+        serde_json::from_slice(&json).map_err(|e| format!("Cache snapshot is malformed: {e}"))?;
+    let manifest = snapshot.manifest;
+    if manifest.format != SNAPSHOT_FORMAT {
+        return Err(format!("Unsupported snapshot format '{}'", manifest.format));
+    }
+    if manifest.version == 0 || manifest.version > SNAPSHOT_VERSION {
+        return Err(format!(
+            "Cache snapshot version {} is not supported by this app (max {SNAPSHOT_VERSION})",
+            manifest.version
+        ));
+    }
+    if manifest.key_count != snapshot.entries.len() {
+        return Err(format!(
+            "Cache snapshot manifest lists {} keys but contains {}",
+            manifest.key_count,
+            snapshot.entries.len()
+        ));
+    }
+    if snapshot.entries.keys().any(|key| key.is_empty()) {
+        return Err("Cache snapshot contains an empty key".to_string());
+    }
+    Ok((manifest, snapshot.entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> Map<String, Value> {
+        let mut entries = Map::new();
+        entries.insert("feed:world".into(), json!({"updatedAt": 1, "data": [1, 2]}));
+        entries.insert("summary:brief".into(), json!({"updatedAt": 2, "data": "x"}));
+        entries
+    }
+
+    #[test]
+    fn snapshot_round_trips() {
+        let manifest = SnapshotManifest::new("2.5.8", "tech", 2, 42);
+        let bytes = encode_snapshot(manifest.clone(), sample()).unwrap();
+        assert!(bytes.starts_with(&GZIP_MAGIC));
+        let (decoded, entries) = decode_snapshot(&bytes).unwrap();
+        assert_eq!(decoded, manifest);
+        assert_eq!(entries, sample());
+    }
+
+    #[test]
+    fn rejects_malformed_snapshots() {
+        assert!(decode_snapshot(b"{\"manifest\":{}}")
+            .unwrap_err()
+            .contains("expected a .json.gz"));
+
+        let miscounted =
+            encode_snapshot(SnapshotManifest::new("2.5.8", "full", 5, 0), sample()).unwrap();
+        assert!(decode_snapshot(&miscounted)
+            .unwrap_err()
+            .contains("lists 5 keys"));
+
+        let mut future = SnapshotManifest::new("9.0.0", "full", 2, 0);
+        future.version = SNAPSHOT_VERSION + 1;
+        let future = encode_snapshot(future, sample()).unwrap();
+        assert!(decode_snapshot(&future)
+            .unwrap_err()
+            .contains("not supported"));
+
+        let truncated =
+            encode_snapshot(SnapshotManifest::new("2.5.8", "full", 2, 0), sample()).unwrap();
+        assert!(decode_snapshot(&truncated[..truncated.len() / 2]).is_err());
+    }
+}
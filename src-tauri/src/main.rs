@@ -1,102 +1,334 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::collections::HashMap;
+mod cache_crypto;
+mod cache_limits;
+mod cache_namespaces;
+mod cache_snapshot;
+mod persistent_cache;
+mod secret_audit;
+mod secret_metadata;
+mod secret_profiles;
+mod secret_registry;
+mod secret_validation;
+mod secrets_vault;
+#[cfg(test)]
+mod test_support;
+
+use std::collections::{HashMap, VecDeque};
 use std::env;
 use std::fs::{self, File, OpenOptions};
 use std::io::Write;
+use std::net::{Ipv4Addr, TcpListener};
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command, Stdio};
-use std::sync::Mutex;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use keyring::Entry;
 use reqwest::Url;
 use serde::Serialize;
-use serde_json::{Map, Value};
+use serde_json::Value;
 use tauri::menu::{AboutMetadata, Menu, MenuItem, PredefinedMenuItem, Submenu};
-use tauri::{AppHandle, Manager, RunEvent, Webview, WebviewUrl, WebviewWindowBuilder, WindowEvent};
-
-const LOCAL_API_PORT: &str = "46123";
+use tauri::{
+    AppHandle, Emitter, Manager, RunEvent, Webview, WebviewUrl, WebviewWindowBuilder, WindowEvent,
+};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use cache_crypto::CacheCipher;
+use cache_limits::{CacheWriteError, CacheWriteRefusal, RefusalCode};
+use cache_namespaces::CacheNamespaces;
+use cache_snapshot::SnapshotManifest;
+use persistent_cache::{
+    format_eviction, CacheChange, CacheEntryInfo, CacheHistoryEntry, CacheStats, ImportMode,
+    ImportReport, PersistentCache,
+};
+use secret_audit::{AuditEntry, AuditOperation};
+use secret_metadata::{MaskedSecret, SecretStatus};
+use secret_profiles::{ProfileList, ProfileVault};
+use secret_registry::SecretKeyInfo;
+use secret_validation::{SecretValidation, SecretValidator, ValidationStatus};
+
+const LOCAL_API_DEFAULT_PORT: u16 = 46123;
+/// Loopback ports the sidecar may use when the default is taken. The CSP in
+/// tauri.conf.json and index.html allows exactly these.
+const LOCAL_API_PORTS: std::ops::RangeInclusive<u16> = 46123..=46127;
 const KEYRING_SERVICE: &str = "world-monitor";
+const CACHE_KEY_ENTRY: &str = "cache-key";
 const LOCAL_API_LOG_FILE: &str = "local-api.log";
 const DESKTOP_LOG_FILE: &str = "desktop.log";
+const CACHE_CHANGED_EVENT: &str = "cache-entry-changed";
+const LOCAL_API_STATUS_EVENT: &str = "local-api-status";
+const SIDECAR_HEALTH_INTERVAL: Duration = Duration::from_secs(5);
+const SIDECAR_HEALTH_TIMEOUT: Duration = Duration::from_secs(3);
+const SIDECAR_HEALTH_MAX_FAILURES: u32 = 3;
+const SIDECAR_STARTUP_GRACE: Duration = Duration::from_secs(15);
+const SIDECAR_RESTART_BASE_DELAY: Duration = Duration::from_secs(1);
+const SIDECAR_RESTART_MAX_DELAY: Duration = Duration::from_secs(30);
+const SIDECAR_CRASH_LOOP_WINDOW: Duration = Duration::from_secs(120);
+const SIDECAR_CRASH_LOOP_MAX_RESTARTS: usize = 5;
+const SIDECAR_SHUTDOWN_REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+const SIDECAR_ENV_UPDATE_TIMEOUT: Duration = Duration::from_secs(3);
+const SIDECAR_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+const CACHE_EVICTION_INTERVAL: Duration = Duration::from_secs(30 * 60);
+const CACHE_FLUSH_DEBOUNCE: Duration = Duration::from_secs(2);
+const CACHE_FLUSH_MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+const CACHE_SLOW_FLUSH: Duration = Duration::from_millis(250);
 const MENU_FILE_SETTINGS_ID: &str = "file.settings";
 const MENU_HELP_GITHUB_ID: &str = "help.github";
 #[cfg(feature = "devtools")]
 const MENU_HELP_DEVTOOLS_ID: &str = "help.devtools";
 const TRUSTED_WINDOWS: [&str; 3] = ["main", "settings", "live-channels"];
-const SUPPORTED_SECRET_KEYS: [&str; 22] = [
-    "GROQ_API_KEY",
-    "OPENROUTER_API_KEY",
-    "FRED_API_KEY",
-    "EIA_API_KEY",
-    "CLOUDFLARE_API_TOKEN",
-    "ACLED_ACCESS_TOKEN",
-    "URLHAUS_AUTH_KEY",
-    "OTX_API_KEY",
-    "ABUSEIPDB_API_KEY",
-    "WINGBITS_API_KEY",
-    "WS_RELAY_URL",
-    "VITE_OPENSKY_RELAY_URL",
-    "OPENSKY_CLIENT_ID",
-    "OPENSKY_CLIENT_SECRET",
-    "AISSTREAM_API_KEY",
-    "VITE_WS_RELAY_URL",
-    "FINNHUB_API_KEY",
-    "NASA_FIRMS_API_KEY",
-    "OLLAMA_API_URL",
-    "OLLAMA_MODEL",
-    "WORLDMONITOR_API_KEY",
-    "WTO_API_KEY",
-];
-
 #[derive(Default)]
 struct LocalApiState {
     child: Mutex<Option<Child>>,
     token: Mutex<Option<String>>,
+    /// Loopback port the sidecar was told to bind; kept across restarts when possible.
+    port: Mutex<Option<u16>>,
+    /// Set once the app is exiting so the supervisor stops respawning the sidecar.
+    shutting_down: AtomicBool,
 }
 
 /// In-memory cache for keychain secrets. Populated once at startup to avoid
 /// repeated macOS Keychain prompts (each `Entry::get_password()` triggers one).
 struct SecretsCache {
+    /// Secrets of the active credential profile.
     secrets: Mutex<HashMap<String, String>>,
+    /// Every profile and where they are persisted. Always lock `secrets` first.
+    vault: Mutex<SecretsVault>,
+    /// Serializes pushes to the sidecar so they land in commit order. Never
+    /// taken while `secrets` is held.
+    sidecar_sync: Mutex<()>,
 }
 
-/// In-memory mirror of persistent-cache.json. The file can grow to 10+ MB,
-/// so reading/parsing/writing it on every IPC call blocks the main thread.
-/// Instead, load once into RAM and serialize writes to preserve ordering.
-struct PersistentCache {
-    data: Mutex<Map<String, Value>>,
-    dirty: Mutex<bool>,
-    write_lock: Mutex<()>,
+struct SecretsVault {
+    backend: SecretsBackend,
+    profiles: ProfileVault,
+}
+
+/// The vault file picked by `preview_secrets_import` and the key derived for
+/// it, so the import that follows does not rerun the KDF.
+#[derive(Default)]
+struct PendingVaultImport(Mutex<Option<(PathBuf, secrets_vault::ExportKey)>>);
+
+enum SecretsBackend {
+    Keyring,
+    /// The keyring could not be read at startup (e.g. the user denied the
+    /// macOS prompt). Nothing is loaded and saves are refused, so an empty
+    /// vault never replaces the real one.
+    KeyringUnavailable(String),
+    /// No persistent OS keyring: a passphrase-sealed file in the app data
+    /// dir, empty and read-only until unlocked from the settings window.
+    File(secrets_vault::FileVault),
+}
+
+/// Returned by `get_secrets_vault_status` and `unlock_secrets_vault`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SecretsVaultStatus {
+    backend: &'static str,
+    locked: bool,
+    /// False until a file vault has been created by its first unlock.
+    exists: bool,
 }
 
 impl SecretsCache {
-    fn load_from_keychain() -> Self {
-        // Try consolidated vault first — single keychain prompt
-        if let Ok(entry) = Entry::new(KEYRING_SERVICE, "secrets-vault") {
-            if let Ok(json) = entry.get_password() {
-                if let Ok(map) = serde_json::from_str::<HashMap<String, String>>(&json) {
-                    let secrets: HashMap<String, String> = map
-                        .into_iter()
-                        .filter(|(k, v)| {
-                            SUPPORTED_SECRET_KEYS.contains(&k.as_str()) && !v.trim().is_empty()
-                        })
-                        .map(|(k, v)| (k, v.trim().to_string()))
-                        .collect();
-                    return SecretsCache {
-                        secrets: Mutex::new(secrets),
-                    };
+    /// Use the OS keyring unless a file vault already exists at
+    /// `vault_file`, the platform has no persistent keyring, or the keyring
+    /// service fails outright; then the file vault is used and starts
+    /// locked. The keyring is read once, so macOS prompts at most once.
+    fn load(vault_file: Option<PathBuf>, log: impl Fn(&str, &str)) -> Self {
+        let fallback = match &vault_file {
+            Some(path) if path.exists() => Some("file vault present".to_string()),
+            _ if !keyring_is_persistent() => {
+                Some("no persistent keyring on this platform".to_string())
+            }
+            _ => None,
+        };
+        let (backend, profiles) = match fallback {
+            Some(reason) => (
+                Self::file_backend(vault_file, &reason, &log),
+                ProfileVault::single(HashMap::new()),
+            ),
+            None => match Self::load_from_keychain() {
+                Ok(profiles) => (SecretsBackend::Keyring, profiles),
+                // A denied prompt is not a missing keyring: ask again next
+                // launch rather than move to the file vault for good.
+                Err(KeychainLoadError::Keyring(keyring::Error::NoStorageAccess(err))) => {
+                    log(
+                        "ERROR",
+                        &format!("secrets: keychain access denied ({err}); keys are unavailable until the next launch"),
+                    );
+                    (
+                        SecretsBackend::KeyringUnavailable(format!(
+                            "keychain access was denied ({err}). Restart World Monitor and allow keychain access"
+                        )),
+                        ProfileVault::single(HashMap::new()),
+                    )
+                }
+                // Saving over a vault that cannot be read would replace every
+                // key and profile in it.
+                Err(KeychainLoadError::Damaged(reason)) => {
+                    log(
+                        "ERROR",
+                        &format!("secrets: keychain vault cannot be read ({reason}); saves are refused to preserve it"),
+                    );
+                    (
+                        SecretsBackend::KeyringUnavailable(format!(
+                            "the keychain vault cannot be read ({reason})"
+                        )),
+                        ProfileVault::single(HashMap::new()),
+                    )
                 }
+                Err(KeychainLoadError::Keyring(err)) => (
+                    Self::file_backend(vault_file, &err.to_string(), &log),
+                    ProfileVault::single(HashMap::new()),
+                ),
+            },
+        };
+        SecretsCache {
+            sidecar_sync: Mutex::new(()),
+            secrets: Mutex::new(profiles.active().clone()),
+            vault: Mutex::new(SecretsVault { backend, profiles }),
+        }
+    }
+
+    fn file_backend(
+        vault_file: Option<PathBuf>,
+        reason: &str,
+        log: &impl Fn(&str, &str),
+    ) -> SecretsBackend {
+        match vault_file {
+            Some(path) => {
+                log(
+                    "WARN",
+                    &format!("secrets: using passphrase-protected file vault ({reason})"),
+                );
+                SecretsBackend::File(secrets_vault::FileVault::new(
+                    path,
+                    secrets_vault::KdfParams::default(),
+                ))
+            }
+            None => {
+                log("ERROR", &format!("secrets: keyring unavailable ({reason}) and no app data dir for a file vault"));
+                SecretsBackend::KeyringUnavailable(format!("keyring unavailable ({reason})"))
             }
         }
+    }
+
+    /// Persist `proposed` as the active profile; callers commit it to
+    /// `secrets` only on success.
+    fn save(&self, proposed: &HashMap<String, String>) -> Result<(), String> {
+        let mut vault = self.vault.lock().unwrap_or_else(|e| e.into_inner());
+        let mut next = vault.profiles.clone();
+        next.set_active_secrets(proposed.clone(), secret_metadata::now_ms());
+        vault.persist(&next)?;
+        vault.profiles = next;
+        Ok(())
+    }
+
+    /// Apply `change` to a copy of the active secrets, persist it, then
+    /// commit it to `secrets`.
+    fn update_secrets(
+        &self,
+        change: impl FnOnce(&mut HashMap<String, String>),
+    ) -> Result<(), String> {
+        let mut secrets = self
+            .secrets
+            .lock()
+            .map_err(|_| "Lock poisoned".to_string())?;
+        let mut proposed = secrets.clone();
+        change(&mut proposed);
+        self.save(&proposed)?;
+        *secrets = proposed;
+        Ok(())
+    }
+
+    /// Apply `change` to the profiles, persist them and make `secrets` the
+    /// (possibly new) active profile. `secrets` must be this cache's locked
+    /// `secrets`.
+    fn update_profiles(
+        &self,
+        secrets: &mut HashMap<String, String>,
+        change: impl FnOnce(&mut ProfileVault) -> Result<(), String>,
+    ) -> Result<ProfileList, String> {
+        let mut vault = self.vault.lock().unwrap_or_else(|e| e.into_inner());
+        let mut next = vault.profiles.clone();
+        change(&mut next)?;
+        vault.persist(&next)?;
+        *secrets = next.active().clone();
+        vault.profiles = next;
+        Ok(vault.profiles.list())
+    }
+
+    fn secret_status(&self) -> Vec<SecretStatus> {
+        self.vault
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .profiles
+            .status(&secret_registry::get().keys(), secret_metadata::now_ms())
+    }
+
+    /// Masked view of every supported key in the active profile.
+    fn masked_secrets(&self) -> Vec<MaskedSecret> {
+        let registry = secret_registry::get();
+        let secrets = self.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        self.secret_status()
+            .into_iter()
+            .filter_map(|status| {
+                let info = registry.info(&status.key)?;
+                let value = secrets.get(&status.key).map(String::as_str);
+                let valid = value.is_some_and(|v| registry.check_value(&info.key, v).is_ok());
+                Some(MaskedSecret::new(status, info.kind, value, valid))
+            })
+            .collect()
+    }
+
+    fn profiles(&self) -> ProfileList {
+        self.vault
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .profiles
+            .list()
+    }
+
+    fn status(&self) -> SecretsVaultStatus {
+        match &self.vault.lock().unwrap_or_else(|e| e.into_inner()).backend {
+            SecretsBackend::Keyring => SecretsVaultStatus {
+                backend: "keyring",
+                locked: false,
+                exists: true,
+            },
+            SecretsBackend::KeyringUnavailable(_) => SecretsVaultStatus {
+                backend: "keyring",
+                locked: true,
+                exists: true,
+            },
+            SecretsBackend::File(vault) => SecretsVaultStatus {
+                backend: "file",
+                locked: !vault.is_unlocked(),
+                exists: vault.exists(),
+            },
+        }
+    }
+
+    /// `Err` when the keyring refuses or fails on the first read, or holds
+    /// a vault that cannot be read back; the caller picks the backend from
+    /// that. Only a missing vault entry leads to the migration.
+    fn load_from_keychain() -> Result<ProfileVault, KeychainLoadError> {
+        // Try consolidated vault first — single keychain prompt
+        if let Some(json) = read_keyring_vault()? {
+            return ProfileVault::parse(json.as_bytes(), &secret_registry::get().keys())
+                .map_err(KeychainLoadError::Damaged);
+        }
 
         // Migration: read individual keys (old format), consolidate into vault.
         // This triggers one keychain prompt per key — happens only once.
         let mut secrets = HashMap::new();
-        for key in SUPPORTED_SECRET_KEYS.iter() {
+        for key in secret_registry::get().keys().iter() {
             if let Ok(entry) = Entry::new(KEYRING_SERVICE, key) {
                 if let Ok(value) = entry.get_password() {
                     let trimmed = value.trim().to_string();
@@ -108,71 +340,40 @@ impl SecretsCache {
         }
 
         // Write consolidated vault and clean up individual entries
-        if !secrets.is_empty() {
-            if let Ok(json) = serde_json::to_string(&secrets) {
-                if let Ok(vault_entry) = Entry::new(KEYRING_SERVICE, "secrets-vault") {
-                    if vault_entry.set_password(&json).is_ok() {
-                        for key in SUPPORTED_SECRET_KEYS.iter() {
-                            if let Ok(entry) = Entry::new(KEYRING_SERVICE, key) {
-                                let _ = entry.delete_credential();
-                            }
-                        }
-                    }
+        let profiles = ProfileVault::single(secrets);
+        if !profiles.active().is_empty() && save_vault(&profiles).is_ok() {
+            for key in secret_registry::get().keys().iter() {
+                if let Ok(entry) = Entry::new(KEYRING_SERVICE, key) {
+                    let _ = entry.delete_credential();
                 }
             }
         }
 
-        SecretsCache {
-            secrets: Mutex::new(secrets),
-        }
+        Ok(profiles)
     }
 }
 
-impl PersistentCache {
-    fn load(path: &Path) -> Self {
-        let data = if path.exists() {
-            std::fs::read_to_string(path)
-                .ok()
-                .and_then(|s| serde_json::from_str::<Value>(&s).ok())
-                .and_then(|v| v.as_object().cloned())
-                .unwrap_or_default()
-        } else {
-            Map::new()
-        };
-        PersistentCache {
-            data: Mutex::new(data),
-            dirty: Mutex::new(false),
-            write_lock: Mutex::new(()),
+impl SecretsVault {
+    fn persist(&self, profiles: &ProfileVault) -> Result<(), String> {
+        match &self.backend {
+            SecretsBackend::Keyring => save_vault(profiles),
+            SecretsBackend::KeyringUnavailable(reason) => {
+                Err(format!("Cannot save keys: {reason}"))
+            }
+            SecretsBackend::File(vault) => vault.save(profiles.to_json()?.as_bytes()),
         }
     }
+}
 
-    fn get(&self, key: &str) -> Option<Value> {
-        let data = self.data.lock().unwrap_or_else(|e| e.into_inner());
-        data.get(key).cloned()
-    }
-
-    /// Flush to disk only if dirty. Returns Ok(true) if written.
-    fn flush(&self, path: &Path) -> Result<bool, String> {
-        let _write_guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
-
-        let is_dirty = {
-            let dirty = self.dirty.lock().unwrap_or_else(|e| e.into_inner());
-            *dirty
-        };
-        if !is_dirty {
-            return Ok(false);
-        }
-
-        let data = self.data.lock().unwrap_or_else(|e| e.into_inner());
-        let serialized = serde_json::to_string(&Value::Object(data.clone()))
-            .map_err(|e| format!("Failed to serialize cache: {e}"))?;
-        drop(data);
-        std::fs::write(path, serialized)
-            .map_err(|e| format!("Failed to write cache {}: {e}", path.display()))?;
-        let mut dirty = self.dirty.lock().unwrap_or_else(|e| e.into_inner());
-        *dirty = false;
-        Ok(true)
-    }
+/// Builds without a native store for the platform fall back to keyring's
+/// in-memory mock, which loses everything at exit. Asks the credential
+/// builder only, so no keychain prompt is shown.
+fn keyring_is_persistent() -> bool {
+    matches!(
+        keyring::default::default_credential_builder().persistence(),
+        keyring::credential::CredentialPersistence::UntilDelete
+            | keyring::credential::CredentialPersistence::UntilReboot
+    )
 }
 
 #[derive(Serialize)]
@@ -181,23 +382,180 @@ struct DesktopRuntimeInfo {
     arch: String,
 }
 
-fn save_vault(cache: &HashMap<String, String>) -> Result<(), String> {
-    let json =
-        serde_json::to_string(cache).map_err(|e| format!("Failed to serialize vault: {e}"))?;
-    let entry = Entry::new(KEYRING_SERVICE, "secrets-vault")
-        .map_err(|e| format!("Keyring init failed: {e}"))?;
-    entry
-        .set_password(&json)
-        .map_err(|e| format!("Failed to write vault: {e}"))?;
+/// Payload of the `local-api-status` event sent to the main window whenever
+/// the supervisor restarts (or gives up on) the sidecar.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalApiStatusEvent {
+    state: &'static str,
+    reason: String,
+    attempt: u32,
+    retry_in_ms: u64,
+    port: u16,
+}
+
+/// Why [`SecretsCache::load_from_keychain`] returned no vault.
+enum KeychainLoadError {
+    /// The keyring refused or failed.
+    Keyring(keyring::Error),
+    /// The vault entry exists but it or one of its parts is missing or
+    /// does not parse.
+    Damaged(String),
+}
+
+impl From<keyring::Error> for KeychainLoadError {
+    fn from(err: keyring::Error) -> Self {
+        match err {
+            keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_) => {
+                KeychainLoadError::Keyring(err)
+            }
+            err => KeychainLoadError::Damaged(err.to_string()),
+        }
+    }
+}
+
+/// Keyring entry holding the vault, or pointing at its parts.
+fn vault_entry() -> keyring::Result<Entry> {
+    Entry::new(KEYRING_SERVICE, "secrets-vault")
+}
+
+/// Keyring entry holding one numbered part of a vault `generation`.
+fn vault_part_entry(generation: u64, part: usize) -> keyring::Result<Entry> {
+    Entry::new(
+        KEYRING_SERVICE,
+        &format!("secrets-vault.{generation}.{part}"),
+    )
+}
+
+/// The vault JSON, joined back together if it was stored in parts; `None`
+/// when there is no vault entry.
+fn read_keyring_vault() -> Result<Option<String>, KeychainLoadError> {
+    let main = match vault_entry().and_then(|entry| entry.get_password()) {
+        Ok(main) => main,
+        Err(keyring::Error::NoEntry) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let Some(manifest) = secret_profiles::keyring_manifest(&main) else {
+        return Ok(Some(main));
+    };
+    (1..=manifest.parts)
+        .map(|part| {
+            vault_part_entry(manifest.generation, part)
+                .and_then(|entry| entry.get_password())
+                .map_err(|err| match KeychainLoadError::from(err) {
+                    KeychainLoadError::Damaged(reason) => KeychainLoadError::Damaged(format!(
+                        "part {part} of {}: {reason}",
+                        manifest.parts
+                    )),
+                    other => other,
+                })
+        })
+        .collect::<Result<String, _>>()
+        .map(Some)
+}
+
+/// Write the parts of a new generation, then point the main entry at them,
+/// then delete the previous generation. The main entry is only switched
+/// once every part is in place, so a failed or interrupted save leaves the
+/// previous vault readable.
+fn save_vault(profiles: &ProfileVault) -> Result<(), String> {
+    let main_entry = vault_entry().map_err(|e| format!("Failed to open vault entry: {e}"))?;
+    // Without knowing the live generation, its parts could be overwritten.
+    let previous = match main_entry.get_password() {
+        Ok(main) => secret_profiles::keyring_manifest(&main),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => return Err(format!("Failed to read vault before saving: {e}")),
+    };
+    let generation = previous.map_or(1, |manifest| manifest.generation.wrapping_add(1));
+    let (main, parts) = profiles.to_keyring_entries(generation)?;
+    let delete_parts = |generation: u64, count: usize| {
+        for part in 1..=count {
+            let _ = vault_part_entry(generation, part).and_then(|entry| entry.delete_credential());
+        }
+    };
+    for (index, part) in parts.iter().enumerate() {
+        if let Err(e) =
+            vault_part_entry(generation, index + 1).and_then(|entry| entry.set_password(part))
+        {
+            delete_parts(generation, index);
+            return Err(format!("Failed to write vault part {}: {e}", index + 1));
+        }
+    }
+    if let Err(e) = main_entry.set_password(&main) {
+        delete_parts(generation, parts.len());
+        return Err(format!("Failed to write vault: {e}"));
+    }
+    if let Some(previous) = previous {
+        delete_parts(previous.generation, previous.parts);
+    }
     Ok(())
 }
 
+enum CacheKey {
+    /// Read from the keychain, or just created there.
+    Keychain {
+        key: [u8; cache_crypto::KEY_LEN],
+        created: bool,
+    },
+    /// The keychain cannot hold a key and nothing is encrypted yet, so the
+    /// cache is stored unencrypted, as before encryption existed.
+    Unavailable(String),
+}
+
+/// Data key for the persistent cache, created on first use. `Err` when the
+/// key cannot be had but `has_ciphertext` says the store already holds
+/// values sealed with one: a fresh key would orphan them, and writing
+/// plaintext next to them would mix the two.
+fn load_or_create_cache_key(has_ciphertext: bool) -> Result<CacheKey, String> {
+    let unavailable = |reason: String| {
+        if has_ciphertext {
+            Err(format!(
+                "cache is encrypted but its key cannot be read: {reason}"
+            ))
+        } else {
+            Ok(CacheKey::Unavailable(reason))
+        }
+    };
+    if !keyring_is_persistent() {
+        return unavailable("no persistent keyring on this platform".to_string());
+    }
+    let entry = match Entry::new(KEYRING_SERVICE, CACHE_KEY_ENTRY) {
+        Ok(entry) => entry,
+        Err(e) => return unavailable(format!("keyring init failed: {e}")),
+    };
+    match entry.get_password() {
+        Ok(encoded) => cache_crypto::decode_key(&encoded).map(|key| CacheKey::Keychain { key, created: false }),
+        Err(keyring::Error::NoEntry) if has_ciphertext => Err(
+            "cache is encrypted but its key is missing from the keychain; refusing to create a new one. \
+             Restore the keychain entry, or delete the cache folder to start over"
+                .to_string(),
+        ),
+        Err(keyring::Error::NoEntry) => {
+            let key = CacheCipher::generate_key();
+            match entry.set_password(&cache_crypto::encode_key(&key)) {
+                Ok(()) => Ok(CacheKey::Keychain { key, created: true }),
+                Err(e) => unavailable(format!("failed to store cache encryption key: {e}")),
+            }
+        }
+        Err(e) => unavailable(format!("failed to read cache encryption key: {e}")),
+    }
+}
+
 fn generate_local_token() -> String {
     let mut buf = [0u8; 32];
     getrandom::getrandom(&mut buf).expect("OS CSPRNG unavailable");
     buf.iter().map(|b| format!("{b:02x}")).collect()
 }
 
+/// Payload of `CACHE_CHANGED_EVENT`. Windows ignore changes to other
+/// variants' namespaces.
+#[derive(Clone, Serialize)]
+struct CacheChangeEvent<'a> {
+    namespace: &'a str,
+    #[serde(flatten)]
+    change: &'a CacheChange,
+}
+
 fn require_trusted_window(label: &str) -> Result<(), String> {
     if TRUSTED_WINDOWS.contains(&label) {
         Ok(())
@@ -218,6 +576,19 @@ fn get_local_api_token(webview: Webview, state: tauri::State<'_, LocalApiState>)
         .ok_or_else(|| "Token not generated".to_string())
 }
 
+#[tauri::command]
+fn get_local_api_port(
+    webview: Webview,
+    state: tauri::State<'_, LocalApiState>,
+) -> Result<u16, String> {
+    require_trusted_window(webview.label())?;
+    let port = state
+        .port
+        .lock()
+        .map_err(|_| "Failed to lock local API port".to_string())?;
+    port.ok_or_else(|| "Local API port not assigned".to_string())
+}
+
 #[tauri::command]
 fn get_desktop_runtime_info() -> DesktopRuntimeInfo {
     DesktopRuntimeInfo {
@@ -226,145 +597,1061 @@ fn get_desktop_runtime_info() -> DesktopRuntimeInfo {
     }
 }
 
+/// Every key the vault accepts, with the metadata the settings UI needs.
 #[tauri::command]
-fn list_supported_secret_keys() -> Vec<String> {
-    SUPPORTED_SECRET_KEYS
-        .iter()
-        .map(|key| (*key).to_string())
-        .collect()
+fn list_supported_secret_keys() -> Vec<SecretKeyInfo> {
+    secret_registry::get().entries().to_vec()
 }
 
+/// Presence, last four characters and status of every key; credential
+/// values never leave Rust this way.
 #[tauri::command]
-fn get_secret(
+fn get_masked_secrets(
     webview: Webview,
-    key: String,
     cache: tauri::State<'_, SecretsCache>,
-) -> Result<Option<String>, String> {
+) -> Result<Vec<MaskedSecret>, String> {
     require_trusted_window(webview.label())?;
-    if !SUPPORTED_SECRET_KEYS.contains(&key.as_str()) {
+    Ok(cache.masked_secrets())
+}
+
+/// Plaintext of one stored key. Only the settings window may ask, and the
+/// user must confirm a native prompt that the webview cannot answer.
+#[tauri::command]
+async fn reveal_secret(
+    webview: Webview,
+    app: AppHandle,
+    key: String,
+    cache: tauri::State<'_, SecretsCache>,
+) -> Result<String, String> {
+    let revealed = reveal_confirmed_secret(&webview, &app, &key, &cache).await;
+    record_secret_audit(
+        &app,
+        AuditEntry::new(
+            secret_metadata::now_ms(),
+            AuditOperation::Reveal,
+            webview.label(),
+            &revealed,
+        )
+        .with_key(&key),
+    );
+    revealed
+}
+
+async fn reveal_confirmed_secret(
+    webview: &Webview,
+    app: &AppHandle,
+    key: &str,
+    cache: &SecretsCache,
+) -> Result<String, String> {
+    if webview.label() != "settings" {
+        return Err(format!(
+            "Command not allowed from window '{}'",
+            webview.label()
+        ));
+    }
+    if !secret_registry::get().contains(key) {
         return Err(format!("Unsupported secret key: {key}"));
     }
-    let secrets = cache
+    if !cache
         .secrets
         .lock()
-        .map_err(|_| "Lock poisoned".to_string())?;
-    Ok(secrets.get(&key).cloned())
+        .unwrap_or_else(|e| e.into_inner())
+        .contains_key(key)
+    {
+        return Err(format!("{key} is not set"));
+    }
+    let prompt = app.clone();
+    let parent = webview.window();
+    let message = format!("Show the saved value of {key} in plain text for 30 seconds?");
+    let confirmed = tauri::async_runtime::spawn_blocking(move || {
+        prompt
+            .dialog()
+            .message(message)
+            .title("Reveal API Key")
+            .kind(MessageDialogKind::Warning)
+            .buttons(MessageDialogButtons::OkCancelCustom(
+                "Reveal".to_string(),
+                "Cancel".to_string(),
+            ))
+            .parent(&parent)
+            .blocking_show()
+    })
+    .await
+    .map_err(|e| format!("Confirmation prompt failed: {e}"))?;
+    if !confirmed {
+        return Err("Reveal cancelled".to_string());
+    }
+    // Read again: the key may have changed while the prompt was open.
+    let value = cache
+        .secrets
+        .lock()
+        .map_err(|_| "Lock poisoned".to_string())?
+        .get(key)
+        .cloned()
+        .ok_or_else(|| format!("{key} is not set"))?;
+    append_desktop_log(app, "INFO", &format!("secrets: revealed {key} in settings"));
+    Ok(value)
 }
 
+/// Persists the change, then applies it to the running sidecar before
+/// returning; the report says whether the key is live there.
 #[tauri::command]
-fn get_all_secrets(webview: Webview, cache: tauri::State<'_, SecretsCache>) -> Result<HashMap<String, String>, String> {
+async fn set_secret(
+    webview: Webview,
+    app: AppHandle,
+    key: String,
+    value: String,
+    cache: tauri::State<'_, SecretsCache>,
+) -> Result<SidecarSyncReport, String> {
+    let trimmed = value.trim().to_string();
+    let operation = if trimmed.is_empty() {
+        AuditOperation::Delete
+    } else {
+        AuditOperation::Set
+    };
+    let saved = require_trusted_window(webview.label())
+        .and_then(|()| {
+            if trimmed.is_empty() {
+                require_supported_key(&key)
+            } else {
+                secret_registry::get().check_value(&key, &trimmed)
+            }
+        })
+        .and_then(|()| {
+            cache.update_secrets(|proposed| {
+                if trimmed.is_empty() {
+                    proposed.remove(&key);
+                } else {
+                    proposed.insert(key.clone(), trimmed);
+                }
+            })
+        });
+    record_secret_audit(
+        &app,
+        AuditEntry::new(
+            secret_metadata::now_ms(),
+            operation,
+            webview.label(),
+            &saved,
+        )
+        .with_key(&key),
+    );
+    saved?;
+    Ok(sync_secrets_in_background(&app, vec![key]).await)
+}
+
+/// Check a value against its provider directly, so keys can be verified
+/// while the sidecar is down. Without `value` the stored value is checked.
+/// A definitive result for the stored value is recorded in its metadata.
+#[tauri::command]
+async fn validate_secret(
+    webview: Webview,
+    key: String,
+    value: Option<String>,
+    cache: tauri::State<'_, SecretsCache>,
+) -> Result<SecretValidation, String> {
     require_trusted_window(webview.label())?;
-    Ok(cache
+    let stored = cache
         .secrets
         .lock()
         .unwrap_or_else(|e| e.into_inner())
-        .clone())
+        .clone();
+    let value = match value {
+        Some(value) => value.trim().to_string(),
+        None => stored
+            .get(&key)
+            .cloned()
+            .ok_or_else(|| format!("{key} is not set"))?,
+    };
+    if !value.is_empty() {
+        if let Err(err) = secret_registry::get().check_value(&key, &value) {
+            if !secret_registry::get().contains(&key) {
+                return Err(err);
+            }
+            return Ok(SecretValidation::invalid(err));
+        }
+    }
+    let validator = SecretValidator::new()?;
+    let result = validator.validate(&key, &value, &stored).await;
+    // Only a verdict on the key is recorded: throttled, unreachable and
+    // unchecked results say nothing about it.
+    if matches!(
+        result.status,
+        ValidationStatus::Ok | ValidationStatus::Invalid
+    ) && stored.get(&key) == Some(&value)
+    {
+        let mut secrets = cache
+            .secrets
+            .lock()
+            .map_err(|_| "Lock poisoned".to_string())?;
+        cache.update_profiles(&mut secrets, |profiles| {
+            profiles.record_validation(&key, &value, result.status, secret_metadata::now_ms());
+            Ok(())
+        })?;
+    }
+    Ok(result)
 }
 
+/// Metadata of every supported key in the active profile, without values.
 #[tauri::command]
-fn set_secret(
+fn get_secret_status(
     webview: Webview,
-    key: String,
-    value: String,
     cache: tauri::State<'_, SecretsCache>,
-) -> Result<(), String> {
+) -> Result<Vec<SecretStatus>, String> {
     require_trusted_window(webview.label())?;
-    if !SUPPORTED_SECRET_KEYS.contains(&key.as_str()) {
-        return Err(format!("Unsupported secret key: {key}"));
+    Ok(cache.secret_status())
+}
+
+/// Set (or, for `None`, clear) the expiry reminder date of a stored key.
+#[tauri::command]
+fn set_secret_expiry(
+    webview: Webview,
+    app: AppHandle,
+    key: String,
+    expires_at: Option<i64>,
+    cache: tauri::State<'_, SecretsCache>,
+) -> Result<Vec<SecretStatus>, String> {
+    let saved = require_trusted_window(webview.label()).and_then(|()| {
+        let mut secrets = cache
+            .secrets
+            .lock()
+            .map_err(|_| "Lock poisoned".to_string())?;
+        cache.update_profiles(&mut secrets, |profiles| {
+            profiles.set_expiry(&key, expires_at)
+        })
+    });
+    record_secret_audit(
+        &app,
+        AuditEntry::new(
+            secret_metadata::now_ms(),
+            AuditOperation::SetExpiry,
+            webview.label(),
+            &saved,
+        )
+        .with_key(&key),
+    );
+    saved?;
+    Ok(cache.secret_status())
+}
+
+/// Log a reminder for each stored key that has expired or expires soon.
+fn log_expiring_secrets(app: &AppHandle, cache: &SecretsCache) {
+    for status in cache.secret_status() {
+        if status.expired {
+            append_desktop_log(app, "WARN", &format!("secrets: {} has expired", status.key));
+        } else if status.expires_soon {
+            append_desktop_log(
+                app,
+                "WARN",
+                &format!("secrets: {} expires soon", status.key),
+            );
+        }
     }
-    let mut secrets = cache
-        .secrets
-        .lock()
-        .map_err(|_| "Lock poisoned".to_string())?;
-    let trimmed = value.trim().to_string();
-    // Build proposed state, persist first, then commit to cache
-    let mut proposed = secrets.clone();
-    if trimmed.is_empty() {
-        proposed.remove(&key);
+}
+
+#[tauri::command]
+async fn delete_secret(
+    webview: Webview,
+    app: AppHandle,
+    key: String,
+    cache: tauri::State<'_, SecretsCache>,
+) -> Result<SidecarSyncReport, String> {
+    let saved = require_trusted_window(webview.label())
+        .and_then(|()| require_supported_key(&key))
+        .and_then(|()| {
+            cache.update_secrets(|proposed| {
+                proposed.remove(&key);
+            })
+        });
+    record_secret_audit(
+        &app,
+        AuditEntry::new(
+            secret_metadata::now_ms(),
+            AuditOperation::Delete,
+            webview.label(),
+            &saved,
+        )
+        .with_key(&key),
+    );
+    saved?;
+    Ok(sync_secrets_in_background(&app, vec![key]).await)
+}
+
+fn require_supported_key(key: &str) -> Result<(), String> {
+    if secret_registry::get().contains(key) {
+        Ok(())
     } else {
-        proposed.insert(key, trimmed);
+        Err(format!("Unsupported secret key: {key}"))
     }
-    save_vault(&proposed)?;
-    *secrets = proposed;
-    Ok(())
 }
 
+/// Append to the secret audit log. A log that cannot be written is noted in
+/// the desktop log but does not fail the change itself.
+fn record_secret_audit(app: &AppHandle, entry: AuditEntry) {
+    if let Err(err) =
+        secret_audit_log_path(app).and_then(|path| secret_audit::append_entry(&path, &entry))
+    {
+        append_desktop_log(
+            app,
+            "WARN",
+            &format!("secrets: audit log write failed: {err}"),
+        );
+    }
+}
+
+/// Entries of the secret audit log, newest first.
 #[tauri::command]
-fn delete_secret(webview: Webview, key: String, cache: tauri::State<'_, SecretsCache>) -> Result<(), String> {
+fn get_secret_audit_log(
+    webview: Webview,
+    app: AppHandle,
+    limit: Option<usize>,
+) -> Result<Vec<AuditEntry>, String> {
     require_trusted_window(webview.label())?;
-    if !SUPPORTED_SECRET_KEYS.contains(&key.as_str()) {
-        return Err(format!("Unsupported secret key: {key}"));
+    secret_audit::read_entries(
+        &secret_audit_log_path(&app)?,
+        limit.unwrap_or(secret_audit::DEFAULT_READ_LIMIT),
+    )
+}
+
+#[tauri::command]
+fn get_secrets_vault_status(
+    webview: Webview,
+    cache: tauri::State<'_, SecretsCache>,
+) -> Result<SecretsVaultStatus, String> {
+    require_trusted_window(webview.label())?;
+    Ok(cache.status())
+}
+
+/// Unlock the file vault, creating it under `passphrase` if it does not exist
+/// yet. A no-op when secrets live in the OS keyring.
+#[tauri::command]
+async fn unlock_secrets_vault(
+    webview: Webview,
+    app: AppHandle,
+    cache: tauri::State<'_, SecretsCache>,
+    passphrase: String,
+) -> Result<SecretsVaultStatus, String> {
+    if let Err(err) = require_trusted_window(webview.label()) {
+        let refused: Result<(), String> = Err(err.clone());
+        record_secret_audit(
+            &app,
+            AuditEntry::new(
+                secret_metadata::now_ms(),
+                AuditOperation::Unlock,
+                webview.label(),
+                &refused,
+            ),
+        );
+        return Err(err);
     }
+    // Key derivation takes a noticeable moment; keep it off the async workers.
+    let worker = app.clone();
+    let window = webview.label().to_string();
+    let changed = tauri::async_runtime::spawn_blocking(move || {
+        unlock_file_vault(&worker, &window, &passphrase)
+    })
+    .await
+    .map_err(|e| format!("Vault unlock task failed: {e}"))??;
+    log_expiring_secrets(&app, &cache);
+    if !changed.is_empty() {
+        sync_secrets_in_background(&app, changed).await;
+    }
+    Ok(cache.status())
+}
+
+/// Unlock a locked file vault and load its profiles. Returns the keys that
+/// became available.
+fn unlock_file_vault(
+    app: &AppHandle,
+    window: &str,
+    passphrase: &str,
+) -> Result<Vec<String>, String> {
+    let cache = app.state::<SecretsCache>();
     let mut secrets = cache
         .secrets
         .lock()
         .map_err(|_| "Lock poisoned".to_string())?;
-    let mut proposed = secrets.clone();
-    proposed.remove(&key);
-    save_vault(&proposed)?;
-    *secrets = proposed;
+    let mut guard = cache.vault.lock().unwrap_or_else(|e| e.into_inner());
+    let vault = &mut *guard;
+    let SecretsBackend::File(file) = &mut vault.backend else {
+        return Ok(Vec::new());
+    };
+    if file.is_unlocked() {
+        return Ok(Vec::new());
+    }
+    let created = !file.exists();
+    let opened = file.unlock(passphrase).and_then(|contents| {
+        // Stay locked rather than let the next save overwrite a vault we
+        // could not read.
+        ProfileVault::parse(&contents, &secret_registry::get().keys()).inspect_err(|_| file.lock())
+    });
+    record_secret_audit(
+        app,
+        AuditEntry::new(
+            secret_metadata::now_ms(),
+            AuditOperation::Unlock,
+            window,
+            &opened,
+        ),
+    );
+    let profiles = opened?;
+    *secrets = profiles.active().clone();
+    vault.profiles = profiles;
+    let action = if created { "created" } else { "unlocked" };
+    append_desktop_log(
+        app,
+        "INFO",
+        &format!("secrets: {action} file vault ({} keys)", secrets.len()),
+    );
+    Ok(secrets.keys().cloned().collect())
+}
+
+#[tauri::command]
+fn list_secret_profiles(
+    webview: Webview,
+    cache: tauri::State<'_, SecretsCache>,
+) -> Result<ProfileList, String> {
+    require_trusted_window(webview.label())?;
+    Ok(cache.profiles())
+}
+
+/// Add a credential profile, empty or as a copy of the active one. The
+/// active profile is unchanged.
+#[tauri::command]
+fn create_secret_profile(
+    webview: Webview,
+    app: AppHandle,
+    cache: tauri::State<'_, SecretsCache>,
+    name: String,
+    copy_active: bool,
+) -> Result<ProfileList, String> {
+    let created = require_trusted_window(webview.label()).and_then(|()| {
+        let mut secrets = cache
+            .secrets
+            .lock()
+            .map_err(|_| "Lock poisoned".to_string())?;
+        cache.update_profiles(&mut secrets, |profiles| profiles.create(&name, copy_active))
+    });
+    record_secret_audit(
+        &app,
+        AuditEntry::new(
+            secret_metadata::now_ms(),
+            AuditOperation::CreateProfile,
+            webview.label(),
+            &created,
+        )
+        .with_profile(&name),
+    );
+    let list = created?;
+    append_desktop_log(&app, "INFO", &format!("secrets: created profile {name}"));
+    Ok(list)
+}
+
+/// Make `name` the active credential profile and restart the sidecar so its
+/// environment holds that profile's keys only.
+#[tauri::command]
+async fn switch_secret_profile(
+    webview: Webview,
+    app: AppHandle,
+    cache: tauri::State<'_, SecretsCache>,
+    name: String,
+) -> Result<ProfileList, String> {
+    let switched = require_trusted_window(webview.label()).and_then(|()| {
+        let mut secrets = cache
+            .secrets
+            .lock()
+            .map_err(|_| "Lock poisoned".to_string())?;
+        if cache.profiles().active == name {
+            return Ok(None);
+        }
+        cache
+            .update_profiles(&mut secrets, |profiles| profiles.switch(&name))
+            .map(Some)
+    });
+    if !matches!(switched, Ok(None)) {
+        record_secret_audit(
+            &app,
+            AuditEntry::new(
+                secret_metadata::now_ms(),
+                AuditOperation::SwitchProfile,
+                webview.label(),
+                &switched,
+            )
+            .with_profile(&name),
+        );
+    }
+    let Some(list) = switched? else {
+        return Ok(cache.profiles());
+    };
+    append_desktop_log(
+        &app,
+        "INFO",
+        &format!("secrets: switched to profile {name}"),
+    );
+    // The old sidecar gets up to SIDECAR_SHUTDOWN_TIMEOUT to exit; wait for it
+    // on a blocking thread rather than the main one.
+    let worker = app.clone();
+    let reason = format!("switched to credential profile {name}");
+    let restarted =
+        tauri::async_runtime::spawn_blocking(move || restart_local_api(&worker, &reason))
+            .await
+            .map_err(|e| format!("sidecar restart task failed: {e}"))
+            .and_then(|restarted| restarted);
+    if let Err(err) = restarted {
+        append_desktop_log(
+            &app,
+            "ERROR",
+            &format!("local API sidecar restart failed: {err}"),
+        );
+    }
+    Ok(list)
+}
+
+/// Write the vault, sealed with `passphrase`, to the user's Downloads folder
+/// and return its path.
+#[tauri::command]
+async fn export_secrets_vault(
+    webview: Webview,
+    app: AppHandle,
+    cache: tauri::State<'_, SecretsCache>,
+    passphrase: String,
+) -> Result<String, String> {
+    require_trusted_window(webview.label())?;
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    let export = secrets_vault::VaultExport {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at,
+        secrets: cache
+            .secrets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+    };
+    let key_count = export.secrets.len();
+    // Argon2id takes about a second at the default cost.
+    let bytes = tauri::async_runtime::spawn_blocking(move || {
+        secrets_vault::encode_export(&export, &passphrase, secrets_vault::KdfParams::default())
+    })
+    .await
+    .map_err(|e| format!("Vault export task failed: {e}"))??;
+
+    let path = export_dir(&app)?.join(format!(
+        "world-monitor-secrets-{}.{}",
+        created_at / 1000,
+        secrets_vault::EXPORT_EXTENSION
+    ));
+    secrets_vault::write_private_file(&path, &bytes)?;
+    append_desktop_log(
+        &app,
+        "INFO",
+        &format!("secrets: exported {key_count} keys to {}", path.display()),
+    );
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Ask for a vault file, decrypt it and report, per key, how importing it
+/// would change the current vault. Nothing is written; the file and its key
+/// are kept for `import_secrets_vault`. `None` when the picker is cancelled.
+#[tauri::command]
+async fn preview_secrets_import(
+    webview: Webview,
+    app: AppHandle,
+    cache: tauri::State<'_, SecretsCache>,
+    pending: tauri::State<'_, PendingVaultImport>,
+    passphrase: String,
+) -> Result<Option<Vec<secrets_vault::MergePreviewItem>>, String> {
+    require_trusted_window(webview.label())?;
+    pending.0.lock().unwrap_or_else(|e| e.into_inner()).take();
+    let picker = app.clone();
+    let parent = webview.window();
+    let opened = tauri::async_runtime::spawn_blocking(move || {
+        let Some(picked) = picker
+            .dialog()
+            .file()
+            .set_title("Import Secrets Vault")
+            .add_filter("World Monitor vault", &[secrets_vault::EXPORT_EXTENSION])
+            .set_parent(&parent)
+            .blocking_pick_file()
+        else {
+            return Ok(None);
+        };
+        let path = picked
+            .into_path()
+            .map_err(|e| format!("Unsupported vault file location: {e}"))?;
+        let bytes =
+            fs::read(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let (export, key) = secrets_vault::decode_export_with_key(&bytes, &passphrase)?;
+        Ok::<_, String>(Some((path, export, key)))
+    })
+    .await
+    .map_err(|e| format!("Vault import task failed: {e}"))??;
+    let Some((path, export, key)) = opened else {
+        return Ok(None);
+    };
+    let items = {
+        let secrets = cache.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        secrets_vault::preview_merge(&secrets, &export.secrets, &secret_registry::get().keys())
+    };
+    *pending.0.lock().unwrap_or_else(|e| e.into_inner()) = Some((path, key));
+    Ok(Some(items))
+}
+
+/// Forget the vault file picked by `preview_secrets_import`.
+#[tauri::command]
+fn cancel_secrets_import(
+    webview: Webview,
+    pending: tauri::State<'_, PendingVaultImport>,
+) -> Result<(), String> {
+    require_trusted_window(webview.label())?;
+    pending.0.lock().unwrap_or_else(|e| e.into_inner()).take();
     Ok(())
 }
 
-fn cache_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+/// Import the chosen `keys` from the previewed vault file. Keys missing from
+/// the file, not in the secret key registry or with values the registry
+/// rejects are skipped; returns how many were written.
+#[tauri::command]
+async fn import_secrets_vault(
+    webview: Webview,
+    app: AppHandle,
+    cache: tauri::State<'_, SecretsCache>,
+    pending: tauri::State<'_, PendingVaultImport>,
+    keys: Vec<String>,
+) -> Result<usize, String> {
+    let export = match require_trusted_window(webview.label())
+        .and_then(|()| reopen_previewed_import(&pending))
+    {
+        Ok(export) => export,
+        Err(err) => {
+            let refused: Result<(), String> = Err(err.clone());
+            for key in &keys {
+                record_secret_audit(
+                    &app,
+                    AuditEntry::new(
+                        secret_metadata::now_ms(),
+                        AuditOperation::Import,
+                        webview.label(),
+                        &refused,
+                    )
+                    .with_key(key),
+                );
+            }
+            return Err(err);
+        }
+    };
+    let imported: Vec<String> = keys
+        .into_iter()
+        .filter(|key| {
+            export.secrets.get(key).map(|v| v.trim()).is_some_and(|v| {
+                !v.is_empty() && secret_registry::get().check_value(key, v).is_ok()
+            })
+        })
+        .collect();
+    if !imported.is_empty() {
+        let saved = cache.update_secrets(|proposed| {
+            for key in &imported {
+                proposed.insert(key.clone(), export.secrets[key].trim().to_string());
+            }
+        });
+        for key in &imported {
+            record_secret_audit(
+                &app,
+                AuditEntry::new(
+                    secret_metadata::now_ms(),
+                    AuditOperation::Import,
+                    webview.label(),
+                    &saved,
+                )
+                .with_key(key),
+            );
+        }
+        saved?;
+    }
+    let count = imported.len();
+    append_desktop_log(
+        &app,
+        "INFO",
+        &format!("secrets: imported {count} keys from vault file"),
+    );
+    if !imported.is_empty() {
+        sync_secrets_in_background(&app, imported).await;
+    }
+    Ok(count)
+}
+
+/// Read the previewed vault file again with the key its preview derived.
+/// Consumes the pending import.
+fn reopen_previewed_import(
+    pending: &PendingVaultImport,
+) -> Result<secrets_vault::VaultExport, String> {
+    let (path, key) = pending
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .ok_or_else(|| "No vault file has been previewed".to_string())?;
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    secrets_vault::decode_export_using(&bytes, &key)
+}
+
+/// Downloads folder, or the app data dir where there is none.
+fn export_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .download_dir()
+        .or_else(|_| app.path().app_data_dir())
+        .map_err(|e| format!("Failed to resolve export directory: {e}"))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    Ok(dir)
+}
+
+fn cache_dir_path(app: &AppHandle) -> Result<PathBuf, String> {
     let dir = app
         .path()
         .app_data_dir()
         .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
     std::fs::create_dir_all(&dir)
         .map_err(|e| format!("Failed to create app data directory {}: {e}", dir.display()))?;
-    Ok(dir.join("persistent-cache.json"))
+    Ok(dir)
 }
 
 #[tauri::command]
-fn read_cache_entry(webview: Webview, cache: tauri::State<'_, PersistentCache>, key: String) -> Result<Option<Value>, String> {
+fn read_cache_entry(
+    webview: Webview,
+    namespaces: tauri::State<'_, CacheNamespaces>,
+    namespace: Option<String>,
+    key: String,
+) -> Result<Option<Value>, String> {
     require_trusted_window(webview.label())?;
-    Ok(cache.get(&key))
+    Ok(namespaces.get(namespace.as_deref())?.get(&key))
 }
 
 #[tauri::command]
-fn delete_cache_entry(webview: Webview, cache: tauri::State<'_, PersistentCache>, key: String) -> Result<(), String> {
+fn delete_cache_entry(
+    webview: Webview,
+    namespaces: tauri::State<'_, CacheNamespaces>,
+    namespace: Option<String>,
+    key: String,
+) -> Result<(), String> {
     require_trusted_window(webview.label())?;
-    {
-        let mut data = cache.data.lock().unwrap_or_else(|e| e.into_inner());
-        data.remove(&key);
-    }
-    {
-        let mut dirty = cache.dirty.lock().unwrap_or_else(|e| e.into_inner());
-        *dirty = true;
+    namespaces.get(namespace.as_deref())?.remove(&key)
+}
+
+#[tauri::command]
+fn write_cache_entry(
+    webview: Webview,
+    app: AppHandle,
+    namespaces: tauri::State<'_, CacheNamespaces>,
+    namespace: Option<String>,
+    key: String,
+    value: String,
+) -> Result<(), CacheWriteError> {
+    require_trusted_window(webview.label())?;
+    let cache = namespaces.get(namespace.as_deref())?;
+    // Updates the in-memory mirror; the cache writer thread persists it.
+    for (key, value) in admit_cache_writes(&app, &cache, vec![(key, value)])? {
+        cache.put(key, value)?;
     }
-    // Disk flush deferred to exit handler (cache.flush) — avoids blocking main thread
     Ok(())
 }
 
+/// Check and parse every entry before anything is written, so one refused
+/// entry rejects the whole batch. All refusals are reported, counted and
+/// logged, letting the frontend resubmit the rest.
+fn admit_cache_writes(
+    app: &AppHandle,
+    cache: &PersistentCache,
+    entries: Vec<(String, String)>,
+) -> Result<Vec<(String, Value)>, CacheWriteError> {
+    let mut parsed = Vec::with_capacity(entries.len());
+    let mut refusals = Vec::new();
+    let mut admitted = HashMap::new();
+    for (key, value) in entries {
+        if let Err(refusal) = cache.check_write(&key, value.len(), &admitted) {
+            refusals.push(refusal);
+            continue;
+        }
+        match serde_json::from_str::<Value>(&value) {
+            Ok(parsed_value) => {
+                admitted.insert(key.clone(), value.len() as u64);
+                parsed.push((key, parsed_value));
+            }
+            Err(e) => refusals.push(CacheWriteRefusal::new(
+                RefusalCode::InvalidJson,
+                &key,
+                format!("Invalid cache payload JSON for '{key}': {e}"),
+            )),
+        }
+    }
+    if refusals.is_empty() {
+        return Ok(parsed);
+    }
+    log_cache_refusals(app, cache, &refusals);
+    Err(refusals.into())
+}
+
+/// Count refused writes against `cache` and log each one.
+fn log_cache_refusals(app: &AppHandle, cache: &PersistentCache, refusals: &[CacheWriteRefusal]) {
+    if refusals.is_empty() {
+        return;
+    }
+    let total = cache.record_refusals(refusals);
+    for refusal in refusals {
+        append_desktop_log(
+            app,
+            "WARN",
+            &format!(
+                "cache: refused write ({}): {}; {total} refused since launch",
+                refusal.code.as_str(),
+                refusal.message
+            ),
+        );
+    }
+}
+
 #[tauri::command]
-fn write_cache_entry(webview: Webview, app: AppHandle, cache: tauri::State<'_, PersistentCache>, key: String, value: String) -> Result<(), String> {
+fn read_cache_entries(
+    webview: Webview,
+    namespaces: tauri::State<'_, CacheNamespaces>,
+    namespace: Option<String>,
+    keys: Vec<String>,
+) -> Result<serde_json::Map<String, Value>, String> {
     require_trusted_window(webview.label())?;
-    let parsed_value: Value = serde_json::from_str(&value)
-        .map_err(|e| format!("Invalid cache payload JSON: {e}"))?;
-    let _write_guard = cache.write_lock.lock().unwrap_or_else(|e| e.into_inner());
-    {
-        let mut data = cache.data.lock().unwrap_or_else(|e| e.into_inner());
-        data.insert(key, parsed_value);
-    }
-    {
-        let mut dirty = cache.dirty.lock().unwrap_or_else(|e| e.into_inner());
-        *dirty = true;
-    }
-
-    // Flush synchronously under write lock so concurrent writes cannot reorder.
-    let path = cache_file_path(&app)?;
-    let data = cache.data.lock().unwrap_or_else(|e| e.into_inner());
-    let serialized = serde_json::to_string(&Value::Object(data.clone()))
-        .map_err(|e| format!("Failed to serialize cache: {e}"))?;
-    drop(data);
-    std::fs::write(&path, &serialized)
-        .map_err(|e| format!("Failed to write cache {}: {e}", path.display()))?;
-    {
-        let mut dirty = cache.dirty.lock().unwrap_or_else(|e| e.into_inner());
-        *dirty = false;
+    Ok(namespaces.get(namespace.as_deref())?.get_many(&keys))
+}
+
+#[tauri::command]
+fn write_cache_entries(
+    webview: Webview,
+    app: AppHandle,
+    namespaces: tauri::State<'_, CacheNamespaces>,
+    namespace: Option<String>,
+    entries: HashMap<String, String>,
+) -> Result<(), CacheWriteError> {
+    require_trusted_window(webview.label())?;
+    let cache = namespaces.get(namespace.as_deref())?;
+    let parsed = admit_cache_writes(&app, &cache, entries.into_iter().collect())?;
+    Ok(cache.put_many(parsed)?)
+}
+
+#[tauri::command]
+fn read_cache_history(
+    webview: Webview,
+    namespaces: tauri::State<'_, CacheNamespaces>,
+    namespace: Option<String>,
+    key: String,
+    since: Option<i64>,
+) -> Result<Vec<CacheHistoryEntry>, String> {
+    require_trusted_window(webview.label())?;
+    namespaces.get(namespace.as_deref())?.history(&key, since)
+}
+
+#[tauri::command]
+fn list_cache_entries(
+    webview: Webview,
+    namespaces: tauri::State<'_, CacheNamespaces>,
+    namespace: Option<String>,
+) -> Result<Vec<CacheEntryInfo>, String> {
+    require_trusted_window(webview.label())?;
+    namespaces.get(namespace.as_deref())?.list()
+}
+
+#[tauri::command]
+fn get_cache_stats(
+    webview: Webview,
+    namespaces: tauri::State<'_, CacheNamespaces>,
+    namespace: Option<String>,
+) -> Result<CacheStats, String> {
+    require_trusted_window(webview.label())?;
+    namespaces.get(namespace.as_deref())?.stats()
+}
+
+#[tauri::command]
+fn delete_cache_entries_by_prefix(
+    webview: Webview,
+    app: AppHandle,
+    namespaces: tauri::State<'_, CacheNamespaces>,
+    namespace: Option<String>,
+    prefix: String,
+) -> Result<usize, String> {
+    require_trusted_window(webview.label())?;
+    let removed = namespaces
+        .get(namespace.as_deref())?
+        .remove_prefix(&prefix)?;
+    append_desktop_log(
+        &app,
+        "INFO",
+        &format!("cache: deleted {removed} entries with prefix '{prefix}'"),
+    );
+    Ok(removed)
+}
+
+/// Copy entries shared between variants (e.g. country geometry) from one
+/// namespace to another. Newer entries already in the target are kept.
+#[tauri::command]
+fn copy_cache_entries(
+    webview: Webview,
+    app: AppHandle,
+    namespaces: tauri::State<'_, CacheNamespaces>,
+    from: String,
+    to: String,
+    prefix: String,
+) -> Result<ImportReport, String> {
+    require_trusted_window(webview.label())?;
+    let report = namespaces.copy(&from, &to, &prefix)?;
+    let target = namespaces.get(Some(&to))?;
+    log_cache_refusals(&app, &target, &report.refused);
+    append_desktop_log(
+        &app,
+        "INFO",
+        &format!(
+            "cache: copied prefix '{prefix}' from '{from}' to '{to}': imported={} keptNewer={} refused={}",
+            report.imported,
+            report.kept_newer,
+            report.refused.len()
+        ),
+    );
+    Ok(report)
+}
+
+/// Result of `import_cache_snapshot`: what was in the file and what changed.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheImportResult {
+    manifest: SnapshotManifest,
+    #[serde(flatten)]
+    report: ImportReport,
+}
+
+/// Keep user-supplied text out of file names.
+fn sanitize_file_component(raw: &str) -> String {
+    let cleaned: String = raw
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .take(32)
+        .collect();
+    if cleaned.is_empty() {
+        "unknown".to_string()
+    } else {
+        cleaned
     }
-    Ok(())
+}
+
+/// Write the whole cache of `namespace` (or the current variant) to a
+/// snapshot in the user's Downloads folder and return its path.
+#[tauri::command]
+async fn export_cache_snapshot(
+    webview: Webview,
+    app: AppHandle,
+    namespaces: tauri::State<'_, CacheNamespaces>,
+    namespace: Option<String>,
+) -> Result<String, String> {
+    require_trusted_window(webview.label())?;
+    let cache = namespaces.get(namespace.as_deref())?;
+    let variant = sanitize_file_component(
+        namespace
+            .as_deref()
+            .unwrap_or(namespaces.default_namespace()),
+    );
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    let path = export_dir(&app)?.join(format!(
+        "world-monitor-cache-{variant}-{}.{}",
+        created_at / 1000,
+        cache_snapshot::SNAPSHOT_EXTENSION
+    ));
+    let target = path.clone();
+    let (key_count, written) = tauri::async_runtime::spawn_blocking(move || {
+        let entries = cache.snapshot();
+        let manifest = SnapshotManifest::new(
+            env!("CARGO_PKG_VERSION"),
+            &variant,
+            entries.len(),
+            created_at,
+        );
+        let key_count = manifest.key_count;
+        let bytes = cache_snapshot::encode_snapshot(manifest, entries)?;
+        secrets_vault::write_private_file(&target, &bytes)?;
+        Ok::<_, String>((key_count, bytes.len()))
+    })
+    .await
+    .map_err(|e| format!("Cache export task failed: {e}"))??;
+    append_desktop_log(
+        &app,
+        "INFO",
+        &format!(
+            "cache: exported {key_count} entries ({written} bytes) to {}",
+            path.display()
+        ),
+    );
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Ask for a snapshot file, validate it in full, then merge it into or
+/// replace the cache. A bad file is rejected without touching any entry.
+/// `None` when the picker is cancelled.
+#[tauri::command]
+async fn import_cache_snapshot(
+    webview: Webview,
+    app: AppHandle,
+    namespaces: tauri::State<'_, CacheNamespaces>,
+    namespace: Option<String>,
+    mode: ImportMode,
+) -> Result<Option<CacheImportResult>, String> {
+    require_trusted_window(webview.label())?;
+    let cache = namespaces.get(namespace.as_deref())?;
+    let picker = app.clone();
+    let parent = webview.window();
+    let worker = cache.clone();
+    let imported = tauri::async_runtime::spawn_blocking(move || {
+        let Some(picked) = picker
+            .dialog()
+            .file()
+            .set_title("Import Cache Snapshot")
+            // Dialog filters match the last extension only.
+            .add_filter("World Monitor cache snapshot", &["gz"])
+            .set_parent(&parent)
+            .blocking_pick_file()
+        else {
+            return Ok(None);
+        };
+        let path = picked
+            .into_path()
+            .map_err(|e| format!("Unsupported snapshot file location: {e}"))?;
+        let bytes =
+            fs::read(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let (manifest, entries) = cache_snapshot::decode_snapshot(&bytes)?;
+        Ok::<_, String>(Some((manifest, worker.import(entries, mode))))
+    })
+    .await
+    .map_err(|e| format!("Cache import task failed: {e}"))??;
+    let Some((manifest, report)) = imported else {
+        return Ok(None);
+    };
+    log_cache_refusals(&app, &cache, &report.refused);
+    append_desktop_log(
+        &app,
+        "INFO",
+        &format!(
+            "cache: imported snapshot ({} v{}, variant {}, {} keys) mode={mode:?}: imported={} keptNewer={} removed={} refused={}",
+            manifest.app_version,
+            manifest.version,
+            manifest.variant,
+            manifest.key_count,
+            report.imported,
+            report.kept_newer,
+            report.removed,
+            report.refused.len()
+        ),
+    );
+    Ok(Some(CacheImportResult { manifest, report }))
 }
 
 fn logs_dir_path(app: &AppHandle) -> Result<PathBuf, String> {
@@ -385,6 +1672,10 @@ fn desktop_log_path(app: &AppHandle) -> Result<PathBuf, String> {
     Ok(logs_dir_path(app)?.join(DESKTOP_LOG_FILE))
 }
 
+fn secret_audit_log_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(logs_dir_path(app)?.join(secret_audit::AUDIT_LOG_FILE))
+}
+
 fn append_desktop_log(app: &AppHandle, level: &str, message: &str) {
     let Ok(path) = desktop_log_path(app) else {
         return;
@@ -527,13 +1818,119 @@ async fn fetch_polymarket(webview: Webview, path: String, params: String) -> Res
         .timeout(std::time::Duration::from_secs(10))
         .send()
         .await
-        .map_err(|e| format!("Polymarket fetch failed: {e}"))?;
-    if !resp.status().is_success() {
-        return Err(format!("Polymarket HTTP {}", resp.status()));
-    }
-    resp.text()
+        .map_err(|e| format!("Polymarket fetch failed: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("Polymarket HTTP {}", resp.status()));
+    }
+    resp.text()
+        .await
+        .map_err(|e| format!("Read body failed: {e}"))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CloudApiResponse {
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    body: String,
+}
+
+/// Whether `url` is a keyed `/api/<service>/v1/` endpoint on a World Monitor
+/// host, the only place the World Monitor key may be sent.
+fn is_keyed_cloud_api_url(url: &Url) -> bool {
+    let host = url.host_str().unwrap_or_default();
+    let mut segments = url.path().trim_start_matches('/').split('/');
+    url.scheme() == "https"
+        && (host == "worldmonitor.app" || host.ends_with(".worldmonitor.app"))
+        && segments.next() == Some("api")
+        && segments.next().is_some_and(|service| !service.is_empty())
+        && segments.next() == Some("v1")
+}
+
+/// Cloud fallback for keyed endpoints. The request is made here so the
+/// World Monitor key is attached without ever reaching the webview.
+#[tauri::command]
+async fn fetch_cloud_api(
+    webview: Webview,
+    url: String,
+    method: String,
+    headers: HashMap<String, String>,
+    body: Option<String>,
+    cache: tauri::State<'_, SecretsCache>,
+) -> Result<CloudApiResponse, String> {
+    require_trusted_window(webview.label())?;
+    let url = Url::parse(&url).map_err(|_| "Invalid cloud API URL".to_string())?;
+    if !is_keyed_cloud_api_url(&url) {
+        return Err(format!("Cloud API URL not allowed: {url}"));
+    }
+    let method = reqwest::Method::from_bytes(method.as_bytes())
+        .map_err(|_| format!("Invalid HTTP method: {method}"))?;
+    let key = cache
+        .secrets
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get("WORLDMONITOR_API_KEY")
+        .cloned();
+    let client = reqwest::Client::builder()
+        .use_native_tls()
+        .build()
+        .map_err(|e| format!("HTTP client error: {e}"))?;
+    let mut request = client.request(method, url).timeout(Duration::from_secs(30));
+    for (name, value) in &headers {
+        if !name.eq_ignore_ascii_case("x-worldmonitor-key") {
+            request = request.header(name.as_str(), value.as_str());
+        }
+    }
+    if let Some(key) = key {
+        request = request.header("X-WorldMonitor-Key", key);
+    }
+    if let Some(body) = body {
+        request = request.body(body);
+    }
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| format!("Cloud API request failed: {e}"))?;
+    let status = resp.status().as_u16();
+    let content_type = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = resp
+        .text()
         .await
-        .map_err(|e| format!("Read body failed: {e}"))
+        .map_err(|e| format!("Read body failed: {e}"))?;
+    Ok(CloudApiResponse {
+        status,
+        content_type,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod cloud_api_tests {
+    use super::is_keyed_cloud_api_url;
+    use reqwest::Url;
+
+    #[test]
+    fn only_keyed_endpoints_on_worldmonitor_hosts_are_allowed() {
+        let allowed = |url: &str| is_keyed_cloud_api_url(&Url::parse(url).unwrap());
+        assert!(allowed("https://worldmonitor.app/api/market/v1/quotes"));
+        assert!(allowed(
+            "https://tech.worldmonitor.app/api/intel/v1/brief?x=1"
+        ));
+        assert!(!allowed("http://worldmonitor.app/api/market/v1/quotes"));
+        assert!(!allowed(
+            "https://worldmonitor.app.evil.com/api/market/v1/quotes"
+        ));
+        assert!(!allowed(
+            "https://evilworldmonitor.app/api/market/v1/quotes"
+        ));
+        assert!(!allowed("https://worldmonitor.app/api/market/quotes"));
+        assert!(!allowed("https://worldmonitor.app/api//v1/quotes"));
+    }
 }
 
 fn open_settings_window(app: &AppHandle) -> Result<(), String> {
@@ -876,6 +2273,17 @@ fn start_local_api(app: &AppHandle) -> Result<(), String> {
     if slot.is_some() {
         return Ok(());
     }
+    *slot = Some(spawn_local_api_child(app, &state)?);
+    Ok(())
+}
+
+/// Launch a new sidecar process. The caller must hold `state.child`.
+fn spawn_local_api_child(app: &AppHandle, state: &LocalApiState) -> Result<Child, String> {
+    // Checked under the child lock so a supervisor restart cannot race past
+    // stop_local_api and leave an orphaned sidecar behind.
+    if state.shutting_down.load(Ordering::SeqCst) {
+        return Err("Local API is shutting down".to_string());
+    }
 
     let (script, resource_root) = local_api_paths(app);
     if !script.exists() {
@@ -925,6 +2333,24 @@ fn start_local_api(app: &AppHandle) -> Result<(), String> {
     let local_api_token = token_slot.clone().unwrap();
     drop(token_slot);
 
+    // Another app (or a second variant such as Finance Monitor) may already
+    // hold the default port; fall back to the next free one in range.
+    let mut port_slot = state
+        .port
+        .lock()
+        .map_err(|_| "Failed to lock local API port")?;
+    let preferred_port = port_slot.unwrap_or(LOCAL_API_DEFAULT_PORT);
+    let port = pick_local_api_port(std::iter::once(preferred_port).chain(LOCAL_API_PORTS))?;
+    if port != preferred_port {
+        append_desktop_log(
+            app,
+            "WARN",
+            &format!("port {preferred_port} unavailable, local API sidecar using port {port}"),
+        );
+    }
+    *port_slot = Some(port);
+    drop(port_slot);
+
     let mut cmd = Command::new(&node_binary);
     #[cfg(windows)]
     cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW — hide the node.exe console
@@ -939,7 +2365,7 @@ fn start_local_api(app: &AppHandle) -> Result<(), String> {
         &format!("node args: script={script_for_node} resource_dir={resource_for_node}"),
     );
     cmd.arg(&script_for_node)
-        .env("LOCAL_API_PORT", LOCAL_API_PORT)
+        .env("LOCAL_API_PORT", port.to_string())
         .env("LOCAL_API_RESOURCE_DIR", &resource_for_node)
         .env("LOCAL_API_MODE", "tauri-sidecar")
         .env("LOCAL_API_TOKEN", &local_api_token)
@@ -977,23 +2403,713 @@ fn start_local_api(app: &AppHandle) -> Result<(), String> {
     append_desktop_log(
         app,
         "INFO",
-        &format!("local API sidecar started pid={}", child.id()),
+        &format!("local API sidecar started pid={} port={port}", child.id()),
     );
-    *slot = Some(child);
+    Ok(child)
+}
+
+/// Bind-probe each candidate loopback port in turn and return the first free
+/// one. The probe listener is dropped before the sidecar binds, so a narrow
+/// race remains; the supervisor restarts the sidecar (re-probing) if it
+/// loses it.
+fn pick_local_api_port(candidates: impl IntoIterator<Item = u16>) -> Result<u16, String> {
+    candidates
+        .into_iter()
+        .find(|port| TcpListener::bind((Ipv4Addr::LOCALHOST, *port)).is_ok())
+        .ok_or_else(|| {
+            format!(
+                "No free loopback port for local API in {}-{}",
+                LOCAL_API_PORTS.start(),
+                LOCAL_API_PORTS.end()
+            )
+        })
+}
+
+fn local_api_port(state: &LocalApiState) -> u16 {
+    state
+        .port
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .unwrap_or(LOCAL_API_DEFAULT_PORT)
+}
+
+#[cfg(test)]
+mod local_api_port_tests {
+    use super::pick_local_api_port;
+    use std::net::{Ipv4Addr, TcpListener};
+
+    #[test]
+    fn keeps_preferred_port_when_free() {
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .and_then(|l| l.local_addr())
+            .unwrap()
+            .port();
+        assert_eq!(pick_local_api_port([port]), Ok(port));
+    }
+
+    #[test]
+    fn falls_back_when_preferred_port_is_taken() {
+        let held = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let taken = held.local_addr().unwrap().port();
+        let free = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .and_then(|l| l.local_addr())
+            .unwrap()
+            .port();
+        assert_eq!(pick_local_api_port([taken, free]), Ok(free));
+        assert!(pick_local_api_port([taken]).is_err());
+    }
+}
+
+/// Ask the sidecar to exit via its authenticated shutdown endpoint.
+fn request_local_api_shutdown(port: u16, token: &str) -> Result<(), String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(SIDECAR_SHUTDOWN_REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("HTTP client error: {e}"))?;
+    let url = format!("http://127.0.0.1:{port}/api/local-shutdown");
+    let resp = client
+        .post(&url)
+        .bearer_auth(token)
+        .send()
+        .map_err(|e| format!("shutdown request failed: {e}"))?;
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("shutdown request HTTP {}", resp.status()))
+    }
+}
+
+/// How the running sidecar picked up a secret change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum SidecarSync {
+    /// Applied in place via `/api/local-env-update`.
+    Pushed,
+    /// The push failed, so the sidecar was restarted with the new environment.
+    Restarted,
+    /// No sidecar is running; the next start injects the change.
+    NotRunning,
+    /// Neither the push nor a restart worked; the change is saved but not live.
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SidecarSyncReport {
+    sidecar: SidecarSync,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Set (or, for `None`, unset) each variable in the sidecar's environment
+/// via its authenticated env update endpoint, stopping at the first failure.
+fn push_local_api_env(
+    port: u16,
+    token: &str,
+    changes: &[(&str, Option<&str>)],
+) -> Result<(), String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(SIDECAR_ENV_UPDATE_TIMEOUT)
+        .build()
+        .map_err(|e| format!("HTTP client error: {e}"))?;
+    let url = format!("http://127.0.0.1:{port}/api/local-env-update");
+    for (key, value) in changes {
+        let resp = client
+            .post(&url)
+            .bearer_auth(token)
+            .json(&serde_json::json!({ "key": key, "value": value }))
+            .send()
+            .map_err(|e| format!("env update for {key} failed: {e}"))?;
+        if !resp.status().is_success() {
+            return Err(format!("env update for {key}: HTTP {}", resp.status()));
+        }
+    }
     Ok(())
 }
 
+/// [`sync_secrets_to_local_api`] on a blocking thread: the push can wait on
+/// HTTP timeouts and a restart on the old sidecar's shutdown, and neither
+/// may hold up the main thread.
+async fn sync_secrets_in_background(app: &AppHandle, keys: Vec<String>) -> SidecarSyncReport {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let cache = app.state::<SecretsCache>();
+        sync_secrets_to_local_api(&app, &cache, &keys)
+    })
+    .await
+    .unwrap_or_else(|e| SidecarSyncReport {
+        sidecar: SidecarSync::Failed,
+        error: Some(format!("sidecar sync task failed: {e}")),
+    })
+}
+
+/// Bring the running sidecar's environment in line with the current value
+/// of each of `keys`, restarting the sidecar if the push fails. Must not be
+/// called with `cache.secrets` held: a restart reads it.
+fn sync_secrets_to_local_api(
+    app: &AppHandle,
+    cache: &SecretsCache,
+    keys: &[String],
+) -> SidecarSyncReport {
+    let _serialized = cache.sidecar_sync.lock().unwrap_or_else(|e| e.into_inner());
+    let state = app.state::<LocalApiState>();
+    if state
+        .child
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_none()
+    {
+        return SidecarSyncReport {
+            sidecar: SidecarSync::NotRunning,
+            error: None,
+        };
+    }
+    let values: Vec<(String, Option<String>)> = {
+        let secrets = cache.secrets.lock().unwrap_or_else(|e| e.into_inner());
+        keys.iter()
+            .map(|key| (key.clone(), secrets.get(key).cloned()))
+            .collect()
+    };
+    let changes: Vec<(&str, Option<&str>)> = values
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_deref()))
+        .collect();
+    let token = state
+        .token
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let pushed = match token.as_deref() {
+        Some(token) => push_local_api_env(local_api_port(&state), token, &changes),
+        None => Err("local API token missing".to_string()),
+    };
+    let Err(err) = pushed else {
+        return SidecarSyncReport {
+            sidecar: SidecarSync::Pushed,
+            error: None,
+        };
+    };
+    append_desktop_log(
+        app,
+        "WARN",
+        &format!("secrets: sidecar {err}; restarting it instead"),
+    );
+    match restart_local_api(app, "secrets changed") {
+        Ok(()) => SidecarSyncReport {
+            sidecar: SidecarSync::Restarted,
+            error: None,
+        },
+        Err(restart_err) => {
+            append_desktop_log(
+                app,
+                "ERROR",
+                &format!("local API sidecar restart failed: {restart_err}"),
+            );
+            SidecarSyncReport {
+                sidecar: SidecarSync::Failed,
+                error: Some(format!("{err}; restart failed: {restart_err}")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod local_api_env_tests {
+    use super::push_local_api_env;
+    use crate::test_support::serve;
+
+    const OK: (u16, &str, &str) = (200, "Content-Type: application/json\r\n", r#"{"ok":true}"#);
+
+    #[test]
+    fn pushes_each_change_with_the_token_and_stops_on_rejection() {
+        let (port, server) = serve(&[OK, OK]);
+        push_local_api_env(
+            port,
+            "tok",
+            &[("GROQ_API_KEY", Some("gsk-1")), ("FRED_API_KEY", None)],
+        )
+        .unwrap();
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST /api/local-env-update"));
+        assert!(requests[0]
+            .to_ascii_lowercase()
+            .contains("authorization: bearer tok"));
+        assert!(requests[0].contains(r#""value":"gsk-1""#));
+        assert!(
+            requests[1].contains(r#""key":"FRED_API_KEY""#)
+                && requests[1].contains(r#""value":null"#)
+        );
+
+        let (port, server) = serve(&[(403, OK.1, OK.2)]);
+        let err = push_local_api_env(
+            port,
+            "tok",
+            &[("ROGUE", Some("x")), ("GROQ_API_KEY", Some("y"))],
+        )
+        .unwrap_err();
+        assert!(err.contains("ROGUE") && err.contains("403"));
+        assert_eq!(server.join().unwrap().len(), 1);
+    }
+}
+
+fn wait_for_child_exit(child: &mut Child, timeout: Duration) -> Option<ExitStatus> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Some(status),
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(50)),
+            _ => return None,
+        }
+    }
+}
+
+/// Stop the sidecar, giving it a chance to finish in-flight work first.
+/// Falls back to a hard kill if the shutdown request fails or the process
+/// does not exit within `SIDECAR_SHUTDOWN_TIMEOUT`.
 fn stop_local_api(app: &AppHandle) {
-    if let Ok(state) = app.try_state::<LocalApiState>().ok_or(()) {
-        if let Ok(mut slot) = state.child.lock() {
-            if let Some(mut child) = slot.take() {
-                let _ = child.kill();
-                append_desktop_log(app, "INFO", "local API sidecar stopped");
+    let Some(state) = app.try_state::<LocalApiState>() else {
+        return;
+    };
+    state.shutting_down.store(true, Ordering::SeqCst);
+    let Some(child) = state.child.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+    shut_down_local_api_child(app, &state, child);
+}
+
+/// Stop and start the sidecar so it picks up a changed environment. The
+/// child slot stays locked until the new process is in it, so the
+/// supervisor never sees the gap and does not count it as a crash.
+fn restart_local_api(app: &AppHandle, reason: &str) -> Result<(), String> {
+    let state = app.state::<LocalApiState>();
+    {
+        let mut slot = state.child.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(child) = slot.take() {
+            shut_down_local_api_child(app, &state, child);
+        }
+        *slot = Some(spawn_local_api_child(app, &state)?);
+    }
+    emit_local_api_status(app, "running", reason, 0, Duration::ZERO);
+    Ok(())
+}
+
+fn shut_down_local_api_child(app: &AppHandle, state: &LocalApiState, mut child: Child) {
+    let token = state
+        .token
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+
+    let started = Instant::now();
+    let requested = match token.as_deref() {
+        Some(token) => request_local_api_shutdown(local_api_port(state), token),
+        None => Err("local API token missing".to_string()),
+    };
+    let exited = match requested {
+        Ok(()) => wait_for_child_exit(&mut child, SIDECAR_SHUTDOWN_TIMEOUT),
+        Err(err) => {
+            append_desktop_log(
+                app,
+                "WARN",
+                &format!("local API graceful shutdown unavailable: {err}"),
+            );
+            None
+        }
+    };
+
+    match exited {
+        Some(status) => append_desktop_log(
+            app,
+            "INFO",
+            &format!(
+                "local API sidecar stopped gracefully ({status}) in {}ms",
+                started.elapsed().as_millis()
+            ),
+        ),
+        None => {
+            let _ = child.kill();
+            let _ = child.wait();
+            append_desktop_log(
+                app,
+                "WARN",
+                &format!(
+                    "local API sidecar force-killed after {}ms",
+                    started.elapsed().as_millis()
+                ),
+            );
+        }
+    }
+}
+
+/// Exponential backoff for sidecar restarts: 1s, 2s, 4s, ... capped at 30s.
+fn restart_backoff_delay(attempt: u32) -> Duration {
+    let factor = 1u32 << attempt.saturating_sub(1).min(16);
+    SIDECAR_RESTART_BASE_DELAY
+        .saturating_mul(factor)
+        .min(SIDECAR_RESTART_MAX_DELAY)
+}
+
+/// Drop restarts that fell out of the crash-loop window and report whether
+/// the remaining ones already exhaust the restart budget.
+fn is_crash_looping(restarts: &mut VecDeque<Instant>, now: Instant) -> bool {
+    while let Some(oldest) = restarts.front() {
+        if now.duration_since(*oldest) > SIDECAR_CRASH_LOOP_WINDOW {
+            restarts.pop_front();
+        } else {
+            break;
+        }
+    }
+    restarts.len() >= SIDECAR_CRASH_LOOP_MAX_RESTARTS
+}
+
+#[cfg(test)]
+mod sidecar_supervisor_tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        assert_eq!(restart_backoff_delay(1), Duration::from_secs(1));
+        assert_eq!(restart_backoff_delay(2), Duration::from_secs(2));
+        assert_eq!(restart_backoff_delay(4), Duration::from_secs(8));
+        assert_eq!(restart_backoff_delay(10), SIDECAR_RESTART_MAX_DELAY);
+        assert_eq!(restart_backoff_delay(u32::MAX), SIDECAR_RESTART_MAX_DELAY);
+    }
+
+    #[test]
+    fn crash_loop_counts_only_recent_restarts() {
+        let now = Instant::now() + SIDECAR_CRASH_LOOP_WINDOW * 2;
+        let stale = now - SIDECAR_CRASH_LOOP_WINDOW - Duration::from_secs(1);
+        let mut restarts: VecDeque<Instant> =
+            std::iter::repeat_n(stale, SIDECAR_CRASH_LOOP_MAX_RESTARTS).collect();
+        assert!(!is_crash_looping(&mut restarts, now));
+        assert!(restarts.is_empty());
+
+        restarts.extend(std::iter::repeat_n(now, SIDECAR_CRASH_LOOP_MAX_RESTARTS));
+        assert!(is_crash_looping(&mut restarts, now));
+    }
+}
+
+fn emit_local_api_status(
+    app: &AppHandle,
+    state: &'static str,
+    reason: &str,
+    attempt: u32,
+    retry_in: Duration,
+) {
+    let payload = LocalApiStatusEvent {
+        state,
+        reason: reason.to_string(),
+        attempt,
+        retry_in_ms: retry_in.as_millis() as u64,
+        port: app
+            .try_state::<LocalApiState>()
+            .map(|s| local_api_port(&s))
+            .unwrap_or(LOCAL_API_DEFAULT_PORT),
+    };
+    if let Err(err) = app.emit_to("main", LOCAL_API_STATUS_EVENT, payload) {
+        append_desktop_log(
+            app,
+            "WARN",
+            &format!("failed to emit {LOCAL_API_STATUS_EVENT}: {err}"),
+        );
+    }
+}
+
+/// Sleep in short slices so shutdown is not held up by a pending backoff.
+/// Returns false if the app started shutting down while sleeping.
+fn supervisor_sleep(state: &LocalApiState, duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    while Instant::now() < deadline {
+        if state.shutting_down.load(Ordering::SeqCst) {
+            return false;
+        }
+        thread::sleep(
+            Duration::from_millis(250).min(deadline.saturating_duration_since(Instant::now())),
+        );
+    }
+    !state.shutting_down.load(Ordering::SeqCst)
+}
+
+/// Returns the exit reason if the sidecar is no longer running.
+fn local_api_exit_reason(state: &LocalApiState) -> Option<String> {
+    let mut slot = state.child.lock().unwrap_or_else(|e| e.into_inner());
+    let child = match slot.as_mut() {
+        Some(child) => child,
+        None => return Some("sidecar not running".to_string()),
+    };
+    match child.try_wait() {
+        Ok(None) => None,
+        Ok(Some(status)) => {
+            slot.take();
+            Some(format!("sidecar exited ({status})"))
+        }
+        Err(e) => Some(format!("failed to poll sidecar process: {e}")),
+    }
+}
+
+fn probe_local_api_health(client: &reqwest::blocking::Client, port: u16) -> Result<(), String> {
+    let url = format!("http://127.0.0.1:{port}/api/service-status");
+    let resp = client
+        .get(&url)
+        .send()
+        .map_err(|e| format!("health check failed: {e}"))?;
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("health check HTTP {}", resp.status()))
+    }
+}
+
+fn kill_local_api_child(state: &LocalApiState) {
+    let mut slot = state.child.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(mut child) = slot.take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// Watch the sidecar for crashes and hangs, restarting it with exponential
+/// backoff. Gives up after too many restarts inside the crash-loop window so
+/// a broken install does not spin forever.
+fn supervise_local_api(app: AppHandle) {
+    let client = match reqwest::blocking::Client::builder()
+        .timeout(SIDECAR_HEALTH_TIMEOUT)
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            append_desktop_log(
+                &app,
+                "ERROR",
+                &format!("local API supervisor disabled: HTTP client error: {e}"),
+            );
+            return;
+        }
+    };
+    let state = app.state::<LocalApiState>();
+    let mut restarts: VecDeque<Instant> = VecDeque::new();
+    let mut health_failures = 0u32;
+    let mut last_start = Instant::now();
+
+    loop {
+        if !supervisor_sleep(&state, SIDECAR_HEALTH_INTERVAL) {
+            return;
+        }
+
+        let reason = if let Some(reason) = local_api_exit_reason(&state) {
+            reason
+        } else if last_start.elapsed() < SIDECAR_STARTUP_GRACE {
+            continue;
+        } else {
+            match probe_local_api_health(&client, local_api_port(&state)) {
+                Ok(()) => {
+                    health_failures = 0;
+                    continue;
+                }
+                Err(err) => {
+                    health_failures += 1;
+                    if health_failures < SIDECAR_HEALTH_MAX_FAILURES {
+                        append_desktop_log(
+                            &app,
+                            "WARN",
+                            &format!(
+                                "local API {err} ({health_failures}/{SIDECAR_HEALTH_MAX_FAILURES})"
+                            ),
+                        );
+                        continue;
+                    }
+                    kill_local_api_child(&state);
+                    format!("sidecar unresponsive: {err}")
+                }
+            }
+        };
+        health_failures = 0;
+
+        if state.shutting_down.load(Ordering::SeqCst) {
+            return;
+        }
+        if is_crash_looping(&mut restarts, Instant::now()) {
+            let message = format!(
+                "local API sidecar crash loop: {} restarts within {}s, giving up ({reason})",
+                restarts.len(),
+                SIDECAR_CRASH_LOOP_WINDOW.as_secs()
+            );
+            append_desktop_log(&app, "ERROR", &message);
+            emit_local_api_status(
+                &app,
+                "failed",
+                &reason,
+                restarts.len() as u32,
+                Duration::ZERO,
+            );
+            return;
+        }
+
+        let attempt = restarts.len() as u32 + 1;
+        let delay = restart_backoff_delay(attempt);
+        append_desktop_log(
+            &app,
+            "WARN",
+            &format!(
+                "local API sidecar down ({reason}); restart attempt {attempt} in {}ms",
+                delay.as_millis()
+            ),
+        );
+        emit_local_api_status(&app, "restarting", &reason, attempt, delay);
+        if !supervisor_sleep(&state, delay) {
+            return;
+        }
+
+        restarts.push_back(Instant::now());
+        last_start = Instant::now();
+        match start_local_api(&app) {
+            Ok(()) => {
+                append_desktop_log(
+                    &app,
+                    "INFO",
+                    &format!("local API sidecar restarted (attempt {attempt})"),
+                );
+                emit_local_api_status(&app, "running", &reason, attempt, Duration::ZERO);
+            }
+            Err(err) => {
+                append_desktop_log(
+                    &app,
+                    "ERROR",
+                    &format!("local API sidecar restart failed: {err}"),
+                );
             }
         }
     }
 }
 
+fn spawn_local_api_supervisor(app: &AppHandle) {
+    let handle = app.clone();
+    if let Err(e) = thread::Builder::new()
+        .name("local-api-supervisor".into())
+        .spawn(move || supervise_local_api(handle))
+    {
+        append_desktop_log(
+            app,
+            "ERROR",
+            &format!("failed to spawn local API supervisor: {e}"),
+        );
+    }
+}
+
+/// Writes cache changes to disk at most once per `CACHE_FLUSH_DEBOUNCE`:
+/// the first dirty key starts the window, and everything written during it
+/// goes out in one transaction. Failed flushes are retried with backoff; a
+/// repeated error is logged only on the 1st, 2nd, 4th, 8th... attempt.
+fn spawn_cache_writer(app: &AppHandle, namespace: &str, cache: Arc<PersistentCache>) {
+    let handle = app.clone();
+    if let Err(e) = thread::Builder::new()
+        .name(format!("cache-writer-{namespace}"))
+        .spawn({
+            let namespace = namespace.to_string();
+            move || {
+                let mut failures = 0u32;
+                let mut last_error = String::new();
+                loop {
+                    cache.wait_for_dirty();
+                    thread::sleep(cache_flush_delay(failures));
+                    match cache.flush_pending() {
+                        Ok(report) => {
+                            if failures > 0 {
+                                append_desktop_log(
+                                    &handle,
+                                    "INFO",
+                                    &format!("cache: flush succeeded after {failures} failed attempts ({namespace})"),
+                                );
+                                failures = 0;
+                                last_error.clear();
+                            }
+                            if report.elapsed >= CACHE_SLOW_FLUSH {
+                                append_desktop_log(
+                                    &handle,
+                                    "WARN",
+                                    &format!(
+                                        "cache: slow flush of {} entries took {}ms ({namespace})",
+                                        report.entries,
+                                        report.elapsed.as_millis()
+                                    ),
+                                );
+                            }
+                        }
+                        Err(err) => {
+                            failures = failures.saturating_add(1);
+                            if err != last_error || failures.is_power_of_two() {
+                                append_desktop_log(
+                                    &handle,
+                                    "WARN",
+                                    &format!(
+                                        "{err} ({namespace}; attempt {failures}, retrying in {}s)",
+                                        cache_flush_delay(failures).as_secs()
+                                    ),
+                                );
+                            }
+                            last_error = err;
+                        }
+                    }
+                }
+            }
+        })
+    {
+        append_desktop_log(app, "ERROR", &format!("failed to spawn cache writer thread: {e}"));
+    }
+}
+
+/// Wait before a flush after `failures` failed ones in a row: the debounce
+/// window, doubled per failure up to `CACHE_FLUSH_MAX_BACKOFF`.
+fn cache_flush_delay(failures: u32) -> Duration {
+    CACHE_FLUSH_DEBOUNCE
+        .saturating_mul(1u32 << failures.min(16))
+        .min(CACHE_FLUSH_MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod cache_writer_tests {
+    use super::*;
+
+    #[test]
+    fn cache_flush_retries_back_off() {
+        assert_eq!(cache_flush_delay(0), CACHE_FLUSH_DEBOUNCE);
+        assert_eq!(cache_flush_delay(3), CACHE_FLUSH_DEBOUNCE * 8);
+        assert_eq!(cache_flush_delay(20), CACHE_FLUSH_MAX_BACKOFF);
+    }
+}
+
+/// Periodic TTL/quota pass over the persistent cache. The startup pass runs
+/// inside `PersistentCache::open`; this only logs passes that removed entries.
+fn spawn_cache_eviction(app: &AppHandle) {
+    let handle = app.clone();
+    if let Err(e) = thread::Builder::new()
+        .name("cache-eviction".into())
+        .spawn(move || loop {
+            thread::sleep(CACHE_EVICTION_INTERVAL);
+            let Some(namespaces) = handle.try_state::<CacheNamespaces>() else {
+                continue;
+            };
+            for (namespace, cache) in namespaces.open_stores() {
+                match cache.evict() {
+                    Ok(stats) if stats.removed() > 0 => append_desktop_log(
+                        &handle,
+                        "INFO",
+                        &format!("[{namespace}] {}", format_eviction("periodic", &stats)),
+                    ),
+                    Ok(_) => {}
+                    Err(err) => append_desktop_log(&handle, "WARN", &err),
+                }
+            }
+        })
+    {
+        append_desktop_log(
+            app,
+            "ERROR",
+            &format!("failed to spawn cache eviction thread: {e}"),
+        );
+    }
+}
+
 #[cfg(target_os = "linux")]
 fn resolve_appimage_gio_module_dir() -> Option<PathBuf> {
     let appdir = env::var_os("APPDIR")?;
@@ -1079,18 +3195,42 @@ fn main() {
         .menu(build_app_menu)
         .on_menu_event(handle_menu_event)
         .manage(LocalApiState::default())
-        .manage(SecretsCache::load_from_keychain())
+        .manage(PendingVaultImport::default())
+        .plugin(tauri_plugin_dialog::init())
         .invoke_handler(tauri::generate_handler![
             list_supported_secret_keys,
-            get_secret,
-            get_all_secrets,
+            get_masked_secrets,
+            reveal_secret,
             set_secret,
             delete_secret,
+            get_secret_audit_log,
+            validate_secret,
+            get_secret_status,
+            set_secret_expiry,
+            get_secrets_vault_status,
+            unlock_secrets_vault,
+            list_secret_profiles,
+            create_secret_profile,
+            switch_secret_profile,
+            export_secrets_vault,
+            preview_secrets_import,
+            cancel_secrets_import,
+            import_secrets_vault,
             get_local_api_token,
+            get_local_api_port,
             get_desktop_runtime_info,
             read_cache_entry,
             write_cache_entry,
             delete_cache_entry,
+            read_cache_entries,
+            write_cache_entries,
+            read_cache_history,
+            list_cache_entries,
+            get_cache_stats,
+            delete_cache_entries_by_prefix,
+            copy_cache_entries,
+            export_cache_snapshot,
+            import_cache_snapshot,
             open_logs_folder,
             open_sidecar_log_file,
             open_settings_window_command,
@@ -1099,12 +3239,91 @@ fn main() {
             close_live_channels_window,
             open_url,
             open_youtube_login,
-            fetch_polymarket
+            fetch_polymarket,
+            fetch_cloud_api
         ])
         .setup(|app| {
-            // Load persistent cache into memory (avoids 14MB file I/O on every IPC call)
-            let cache_path = cache_file_path(&app.handle()).unwrap_or_default();
-            app.manage(PersistentCache::load(&cache_path));
+            // One SQLite-backed cache store per variant, each mirrored in
+            // memory. Values are encrypted with a keychain-held key. Without
+            // a keychain the cache is stored unencrypted, unless it already
+            // holds encrypted values, in which case it stays in memory and
+            // leaves them untouched.
+            let log_handle = app.handle().clone();
+            let log = move |level: &str, message: &str| append_desktop_log(&log_handle, level, message);
+            let default_namespace = cache_namespaces::namespace_for_identifier(&app.config().identifier);
+            let namespaces = match cache_dir_path(app.handle()).and_then(|dir| {
+                load_or_create_cache_key(cache_namespaces::holds_sealed_entries(&dir)).map(|key| (dir, key))
+            }) {
+                Ok((dir, CacheKey::Keychain { key, created })) => {
+                    if created {
+                        append_desktop_log(app.handle(), "INFO", "created cache encryption key in keychain");
+                    }
+                    CacheNamespaces::new(Some(dir), default_namespace, Some(CacheCipher::new(&key)), log)
+                }
+                Ok((dir, CacheKey::Unavailable(reason))) => {
+                    append_desktop_log(
+                        app.handle(),
+                        "WARN",
+                        &format!("cache encryption unavailable ({reason}); storing cache unencrypted"),
+                    );
+                    CacheNamespaces::new(Some(dir), default_namespace, None, log)
+                }
+                Err(err) => {
+                    append_desktop_log(app.handle(), "ERROR", &format!("persistent cache disabled: {err}"));
+                    CacheNamespaces::new(None, default_namespace, None, log)
+                }
+            };
+            // Keep every window's view of the cache in sync without polling.
+            // The external-origin login window's capability has no event
+            // permission, so it cannot listen for these.
+            let hook_handle = app.handle().clone();
+            namespaces.set_open_hook(Box::new(move |namespace, cache| {
+                let event_handle = hook_handle.clone();
+                let event_namespace = namespace.to_string();
+                cache.set_change_listener(Box::new(move |change: &CacheChange| {
+                    let event = CacheChangeEvent { namespace: &event_namespace, change };
+                    let _ = event_handle.emit(CACHE_CHANGED_EVENT, event);
+                }));
+                spawn_cache_writer(&hook_handle, namespace, cache.clone());
+            }));
+            // Open the build's own namespace now so the move of a
+            // pre-namespacing store and the startup eviction happen at launch.
+            if let Err(err) = namespaces.get(None) {
+                append_desktop_log(app.handle(), "WARN", &err);
+            }
+            app.manage(namespaces);
+            spawn_cache_eviction(app.handle());
+
+            // Secret keys come from the same registry file the sidecar reads.
+            let registry_path = if cfg!(debug_assertions) {
+                PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(secret_registry::REGISTRY_FILE)
+            } else {
+                app.path()
+                    .resource_dir()
+                    .unwrap_or_else(|_| PathBuf::from("."))
+                    .join(secret_registry::REGISTRY_FILE)
+            };
+            match secret_registry::SecretRegistry::load(&registry_path) {
+                Ok(registry) => secret_registry::init(registry),
+                Err(err) => append_desktop_log(
+                    app.handle(),
+                    "WARN",
+                    &format!("secrets: {err}; using the built-in key registry"),
+                ),
+            }
+
+            // The sidecar is started with whatever secrets are readable now;
+            // a locked file vault contributes none until it is unlocked.
+            let vault_file = app
+                .path()
+                .app_data_dir()
+                .ok()
+                .map(|dir| dir.join(secrets_vault::FILE_NAME));
+            let secrets = SecretsCache::load(vault_file, |level, message| {
+                append_desktop_log(app.handle(), level, message)
+            });
+            log_expiring_secrets(app.handle(), &secrets);
+            app.manage(secrets);
 
             if let Err(err) = start_local_api(&app.handle()) {
                 append_desktop_log(
@@ -1114,6 +3333,7 @@ fn main() {
                 );
                 eprintln!("[tauri] local API sidecar failed to start: {err}");
             }
+            spawn_local_api_supervisor(app.handle());
 
             Ok(())
         })
@@ -1156,10 +3376,22 @@ fn main() {
                     }
                 }
                 RunEvent::ExitRequested { .. } | RunEvent::Exit => {
-                    // Flush in-memory cache to disk before quitting
-                    if let Ok(path) = cache_file_path(app) {
-                        if let Some(cache) = app.try_state::<PersistentCache>() {
-                            let _ = cache.flush(&path);
+                    // Write pending cache entries and checkpoint before quitting
+                    if let Some(namespaces) = app.try_state::<CacheNamespaces>() {
+                        for (namespace, cache) in namespaces.open_stores() {
+                            match cache.flush() {
+                                Ok(report) if report.entries > 0 => append_desktop_log(
+                                    app,
+                                    "INFO",
+                                    &format!(
+                                        "cache: flushed {} pending entries in {}ms on exit ({namespace})",
+                                        report.entries,
+                                        report.elapsed.as_millis()
+                                    ),
+                                ),
+                                Ok(_) => {}
+                                Err(err) => append_desktop_log(app, "WARN", &err),
+                            }
                         }
                     }
                     stop_local_api(app);
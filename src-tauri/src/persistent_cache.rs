@@ -0,0 +1,2352 @@
+//! Desktop persistent cache backed by an embedded SQLite database.
+//!
+//! The frontend stores panel snapshots (`CacheEnvelope` objects) through the
+//! `read_cache_entry` / `write_cache_entry` / `delete_cache_entry` commands.
+//! Entries live in an in-memory mirror that serves reads and absorbs writes;
+//! changed keys are marked dirty and written to SQLite in one transaction by
+//! [`PersistentCache::flush_pending`], which the desktop shell calls from a
+//! debounced background thread and on exit. A crash can lose at most the
+//! writes of the current debounce window.
+//!
+//! Rolling backup generations (`persistent-cache.db.bak`, `.bak.1`) are
+//! written via temp file + fsync + rename. On load the database is validated
+//! and, if it fails, replaced by the newest backup that passes validation.
+//!
+//! Values larger than a few hundred bytes are zstd-compressed behind a
+//! `WMZ1` magic header (inside the encryption envelope when there is one);
+//! rows without the header are legacy plain JSON and load unchanged.
+//!
+//! With a [`CacheCipher`] every value is encrypted before it reaches disk;
+//! plaintext rows from earlier versions are re-encrypted on open and the
+//! file is vacuumed so no plaintext pages linger.
+//!
+//! Entries expire by per-prefix TTL and the cache is held under a byte quota,
+//! evicting least-recently-used entries first. See [`CachePolicy`].
+//!
+//! Keys matching a [`HistoryRule`] also keep earlier versions in a
+//! `cache_history` table, one row per flushed `updatedAt`, for
+//! [`PersistentCache::history`]. History has its own byte quota, trimmed
+//! oldest-first.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::cache_crypto::{self, CacheCipher};
+use crate::cache_limits::{CacheWriteRefusal, WriteLimits, FRONTEND_PREFIXES};
+
+pub(crate) const CACHE_DB_FILE: &str = "persistent-cache.db";
+/// Single-file JSON format used before the SQLite store. Imported once, then
+/// renamed to `persistent-cache.json.migrated`.
+pub(crate) const LEGACY_CACHE_FILE: &str = "persistent-cache.json";
+const BACKUP_GENERATIONS: usize = 2;
+const COMPRESSED_MAGIC: &[u8; 4] = b"WMZ1";
+const ZSTD_LEVEL: i32 = 3;
+/// Below this the zstd frame overhead outweighs any saving.
+const COMPRESS_MIN_BYTES: usize = 256;
+/// Upper bound on a single decompressed value, guarding against a corrupt
+/// or crafted frame expanding without limit.
+const MAX_VALUE_BYTES: u64 = 64 * 1024 * 1024;
+/// A startup backup is taken when the newest generation is older than this,
+/// so a session that never exits cleanly still leaves a recent good copy.
+const BACKUP_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// Optional overrides for [`CachePolicy`], read from the app data dir.
+pub(crate) const CACHE_POLICY_FILE: &str = "cache-policy.json";
+const DEFAULT_MAX_BYTES: u64 = 64 * 1024 * 1024;
+const DEFAULT_HISTORY_MAX_BYTES: u64 = 16 * 1024 * 1024;
+/// Risk scores refresh every 5 minutes (`services/cached-risk-scores.ts`);
+/// two days is enough to compare against the same time yesterday.
+const DEFAULT_HISTORY: &[(&str, HistoryRule)] = &[(
+    "risk-scores:",
+    HistoryRule {
+        max_versions: Some(600),
+        max_age: Some(Duration::from_secs(2 * 24 * 60 * 60)),
+    },
+)];
+
+/// `(level, message)` sink, wired to `desktop.log` by the caller so this
+/// module stays independent of the Tauri app handle.
+pub(crate) type CacheLogger = Box<dyn Fn(&str, &str) + Send + Sync>;
+
+/// A write or delete of one key, reported after the in-memory mirror has
+/// been updated (before it is flushed to disk).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CacheChange {
+    pub key: String,
+    pub updated_at: Option<i64>,
+    pub deleted: bool,
+}
+
+impl CacheChange {
+    fn deleted(key: String) -> Self {
+        CacheChange {
+            key,
+            updated_at: None,
+            deleted: true,
+        }
+    }
+}
+
+/// Called once per change; the desktop shell forwards these as Tauri events.
+pub(crate) type CacheChangeListener = Box<dyn Fn(&CacheChange) + Send + Sync>;
+
+pub(crate) struct PersistentCache {
+    data: Mutex<Map<String, Value>>,
+    /// Keys changed in `data` but not yet written to `db`. Always locked
+    /// after `data`, so a flush sees a consistent mirror/dirty pair.
+    dirty: Mutex<HashSet<String>>,
+    dirty_signal: Condvar,
+    /// Serialized JSON size of each value in `data`, for the per-prefix
+    /// byte caps. Always locked after `data` and `dirty`.
+    sizes: Mutex<HashMap<String, u64>>,
+    /// Held for the whole of a flush or eviction pass, which serializes them:
+    /// each flush writes the mirror's latest values, so later writes always
+    /// win regardless of which thread flushes.
+    db: Mutex<Connection>,
+    flush_stats: Mutex<FlushStats>,
+    /// Database file path; `None` when running in memory only.
+    path: Option<PathBuf>,
+    /// Last read time per key since the previous eviction pass. Kept in
+    /// memory so reads never hit the disk; persisted when eviction runs.
+    accessed: Mutex<HashMap<String, i64>>,
+    policy: CachePolicy,
+    /// `None` stores values as plain JSON text.
+    cipher: Option<CacheCipher>,
+    listener: OnceLock<CacheChangeListener>,
+    refused_writes: AtomicU64,
+    /// Set once `flush` has taken its backup generation. The app flushes on
+    /// more than one exit event, and a second backup would only rotate out
+    /// the one from before this session.
+    exit_backup_taken: AtomicBool,
+}
+
+/// Expiry and size limits applied by [`PersistentCache::evict`].
+#[derive(Debug, Clone)]
+pub(crate) struct CachePolicy {
+    /// `(key prefix, ttl)`; the longest matching prefix wins.
+    pub ttls: Vec<(String, Duration)>,
+    /// Upper bound on the summed size of stored values, in bytes.
+    pub max_bytes: u64,
+    /// `(key prefix, rule)` for keys that keep earlier versions; the longest
+    /// matching prefix wins.
+    pub history: Vec<(String, HistoryRule)>,
+    /// Upper bound on the summed size of history rows, in bytes. Kept apart
+    /// from `max_bytes` so old versions never evict current entries.
+    pub history_max_bytes: u64,
+    /// Admission checks for writes from the webview.
+    pub limits: WriteLimits,
+}
+
+/// How many earlier versions of a key to keep. Either limit may be absent,
+/// but not both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct HistoryRule {
+    pub max_versions: Option<usize>,
+    pub max_age: Option<Duration>,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        CachePolicy {
+            ttls: FRONTEND_PREFIXES
+                .iter()
+                .filter_map(|(prefix, ttl)| Some(((*prefix).to_string(), (*ttl)?)))
+                .collect(),
+            max_bytes: DEFAULT_MAX_BYTES,
+            history: DEFAULT_HISTORY
+                .iter()
+                .map(|(prefix, rule)| ((*prefix).to_string(), *rule))
+                .collect(),
+            history_max_bytes: DEFAULT_HISTORY_MAX_BYTES,
+            limits: WriteLimits::default(),
+        }
+    }
+}
+
+/// On-disk shape of `cache-policy.json`. A TTL of 0 disables expiry for
+/// that prefix; a history rule with neither limit set disables history.
+/// `allowedKeyPrefixes` extends the built-in whitelist; a `prefixLimits`
+/// cap of 0 removes that prefix's cap.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachePolicyFile {
+    max_bytes: Option<u64>,
+    #[serde(default)]
+    ttl_seconds: HashMap<String, u64>,
+    #[serde(default)]
+    history: HashMap<String, HistoryRuleFile>,
+    history_max_bytes: Option<u64>,
+    max_entry_bytes: Option<u64>,
+    #[serde(default)]
+    entry_limits: HashMap<String, u64>,
+    #[serde(default)]
+    prefix_limits: HashMap<String, u64>,
+    #[serde(default)]
+    allowed_key_prefixes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryRuleFile {
+    max_versions: Option<usize>,
+    max_age_seconds: Option<u64>,
+}
+
+impl CachePolicy {
+    /// Defaults, with any overrides from `cache-policy.json` in `dir` applied.
+    pub(crate) fn load(dir: &Path, log: &CacheLogger) -> Self {
+        let mut policy = CachePolicy::default();
+        let path = dir.join(CACHE_POLICY_FILE);
+        let Ok(raw) = fs::read_to_string(&path) else {
+            return policy;
+        };
+        let overrides: CachePolicyFile = match serde_json::from_str(&raw) {
+            Ok(file) => file,
+            Err(e) => {
+                log("WARN", &format!("ignoring invalid {}: {e}", path.display()));
+                return policy;
+            }
+        };
+        if let Some(max_bytes) = overrides.max_bytes {
+            policy.max_bytes = max_bytes;
+        }
+        for (prefix, secs) in overrides.ttl_seconds {
+            policy.ttls.retain(|(existing, _)| *existing != prefix);
+            if secs > 0 {
+                policy.ttls.push((prefix, Duration::from_secs(secs)));
+            }
+        }
+        if let Some(history_max_bytes) = overrides.history_max_bytes {
+            policy.history_max_bytes = history_max_bytes;
+        }
+        for (prefix, rule) in overrides.history {
+            policy.history.retain(|(existing, _)| *existing != prefix);
+            let rule = HistoryRule {
+                max_versions: rule.max_versions.filter(|n| *n > 0),
+                max_age: rule
+                    .max_age_seconds
+                    .filter(|secs| *secs > 0)
+                    .map(Duration::from_secs),
+            };
+            if rule.max_versions.is_some() || rule.max_age.is_some() {
+                policy.history.push((prefix, rule));
+            }
+        }
+        let limits = &mut policy.limits;
+        if let Some(max_entry_bytes) = overrides.max_entry_bytes {
+            limits.max_entry_bytes = max_entry_bytes;
+        }
+        for (prefix, max) in overrides.entry_limits {
+            limits
+                .entry_limits
+                .retain(|(existing, _)| *existing != prefix);
+            limits.entry_limits.push((prefix, max));
+        }
+        for (prefix, max) in overrides.prefix_limits {
+            limits
+                .prefix_limits
+                .retain(|(existing, _)| *existing != prefix);
+            if max > 0 {
+                limits.prefix_limits.push((prefix, max));
+            }
+        }
+        for prefix in overrides.allowed_key_prefixes {
+            if !prefix.is_empty() && !limits.allowed_prefixes.contains(&prefix) {
+                limits.allowed_prefixes.push(prefix);
+            }
+        }
+        policy
+    }
+
+    fn ttl_for(&self, key: &str) -> Option<Duration> {
+        self.ttls
+            .iter()
+            .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, ttl)| *ttl)
+    }
+
+    fn history_for(&self, key: &str) -> Option<HistoryRule> {
+        self.history
+            .iter()
+            .filter(|(prefix, _)| key.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, rule)| *rule)
+    }
+}
+
+/// Outcome of one eviction pass, reported in the desktop log.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EvictionStats {
+    pub expired: usize,
+    pub evicted: usize,
+    pub freed_bytes: u64,
+    pub remaining: usize,
+    pub remaining_bytes: u64,
+}
+
+impl EvictionStats {
+    pub(crate) fn removed(&self) -> usize {
+        self.expired + self.evicted
+    }
+}
+
+/// One row of the settings window's Storage listing.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CacheEntryInfo {
+    pub key: String,
+    pub size_bytes: u64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CacheStats {
+    pub entry_count: usize,
+    pub total_bytes: u64,
+    /// Configured quota, so the UI can show usage against it.
+    pub max_bytes: u64,
+    pub last_flush_ms: u64,
+    pub max_flush_ms: u64,
+    pub encrypted: bool,
+    /// Webview writes refused by [`WriteLimits`] since launch.
+    pub refused_writes: u64,
+}
+
+/// One retained version of a key, as returned by
+/// [`PersistentCache::history`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CacheHistoryEntry {
+    pub updated_at: i64,
+    pub value: Value,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct FlushStats {
+    last_ms: u64,
+    max_ms: u64,
+}
+
+/// How an imported snapshot combines with what is already cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum ImportMode {
+    /// Keep local entries that are newer than the snapshot's copy.
+    Merge,
+    /// Make the cache an exact copy of the snapshot.
+    Replace,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportReport {
+    pub imported: usize,
+    /// Merge only: snapshot entries older than the local copy.
+    pub kept_newer: usize,
+    /// Replace only: local entries absent from the snapshot.
+    pub removed: usize,
+    /// Entries the write limits turned away.
+    pub refused: Vec<CacheWriteRefusal>,
+}
+
+/// Result of writing the dirty set to disk.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FlushReport {
+    pub entries: usize,
+    pub elapsed: Duration,
+}
+
+struct EntryUsage {
+    key: String,
+    updated_at: i64,
+    last_used: i64,
+    size: u64,
+}
+
+/// Pick the keys to drop: everything past its TTL, then least-recently-used
+/// entries until the remainder fits in `max_bytes`.
+fn plan_eviction(
+    policy: &CachePolicy,
+    mut entries: Vec<EntryUsage>,
+    now: i64,
+) -> (Vec<String>, EvictionStats) {
+    let mut stats = EvictionStats::default();
+    let mut doomed = Vec::new();
+    entries.retain(|entry| {
+        let expired = policy
+            .ttl_for(&entry.key)
+            .is_some_and(|ttl| now.saturating_sub(entry.updated_at) > ttl.as_millis() as i64);
+        if expired {
+            stats.expired += 1;
+            stats.freed_bytes += entry.size;
+            doomed.push(entry.key.clone());
+        }
+        !expired
+    });
+
+    let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+    if total > policy.max_bytes {
+        entries.sort_by_key(|entry| entry.last_used);
+        let mut kept = entries.len();
+        for entry in &entries {
+            if total <= policy.max_bytes {
+                break;
+            }
+            total -= entry.size;
+            stats.evicted += 1;
+            stats.freed_bytes += entry.size;
+            doomed.push(entry.key.clone());
+            kept -= 1;
+        }
+        stats.remaining = kept;
+    } else {
+        stats.remaining = entries.len();
+    }
+    stats.remaining_bytes = total;
+    (doomed, stats)
+}
+
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// `updatedAt` stamped by the frontend's `setPersistentCache`, if present.
+pub(crate) fn envelope_updated_at(value: &Value) -> Option<i64> {
+    value.get("updatedAt").and_then(Value::as_i64)
+}
+
+/// Length of `value` serialized as JSON, counted without building the string.
+fn json_size(value: &Value) -> u64 {
+    struct Counter(u64);
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len() as u64;
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut counter = Counter(0);
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS cache_entries (
+             key TEXT PRIMARY KEY NOT NULL,
+             value TEXT NOT NULL,
+             updated_at INTEGER NOT NULL
+         );",
+    )?;
+    // Incremental migrations keyed on `user_version`, so databases restored
+    // from an older backup generation are upgraded in place.
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version < 1 {
+        conn.execute_batch(
+            "ALTER TABLE cache_entries ADD COLUMN accessed_at INTEGER NOT NULL DEFAULT 0;
+             PRAGMA user_version = 1;",
+        )?;
+    }
+    if version < 2 {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS cache_history (
+                 key TEXT NOT NULL,
+                 updated_at INTEGER NOT NULL,
+                 value NOT NULL,
+                 PRIMARY KEY (key, updated_at)
+             );
+             PRAGMA user_version = 2;",
+        )?;
+    }
+    Ok(())
+}
+
+fn open_database(path: &Path) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    // WAL + NORMAL keeps the database consistent across crashes and power
+    // loss; at most the last few committed writes can be rolled back.
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    // Overwrite freed pages so replaced plaintext does not survive on disk.
+    conn.pragma_update(None, "secure_delete", "ON")?;
+    conn.busy_timeout(std::time::Duration::from_secs(5))?;
+    init_schema(&conn)?;
+    Ok(conn)
+}
+
+/// Whether the database at `db_path` holds any encrypted value, so a missing
+/// key can be told apart from a store that never had one. Nothing is created.
+pub(crate) fn holds_sealed_entries(db_path: &Path) -> bool {
+    if !db_path.is_file() {
+        return false;
+    }
+    let Ok(conn) = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_WRITE) else {
+        return false;
+    };
+    ["cache_entries", "cache_history"].iter().any(|table| {
+        conn.query_row(
+            &format!("SELECT EXISTS(SELECT 1 FROM {table} WHERE substr(value, 1, 4) = ?1)"),
+            params![&cache_crypto::MAGIC[..]],
+            |row| row.get::<_, bool>(0),
+        )
+        .unwrap_or(false)
+    })
+}
+
+fn open_in_memory() -> Connection {
+    let conn = Connection::open_in_memory().expect("SQLite in-memory database unavailable");
+    init_schema(&conn).expect("SQLite in-memory schema init failed");
+    conn
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn migrated_path(path: &Path) -> PathBuf {
+    sibling_path(path, ".migrated")
+}
+
+/// Generation 0 is the newest backup (`.bak`), older ones are `.bak.N`.
+fn backup_path(db_path: &Path, generation: usize) -> PathBuf {
+    if generation == 0 {
+        sibling_path(db_path, ".bak")
+    } else {
+        sibling_path(db_path, &format!(".bak.{generation}"))
+    }
+}
+
+fn sync_parent_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+fn validate_database(conn: &Connection) -> Result<(), String> {
+    let result: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if result == "ok" {
+        Ok(())
+    } else {
+        Err(format!("quick_check: {result}"))
+    }
+}
+
+fn open_validated(path: &Path) -> Result<Connection, String> {
+    let conn = open_database(path).map_err(|e| e.to_string())?;
+    validate_database(&conn)?;
+    Ok(conn)
+}
+
+/// Move a failed database (and its WAL/SHM companions) aside so recovery
+/// starts from a clean slate while the bytes stay available for inspection.
+fn quarantine_database(db_path: &Path) {
+    for suffix in ["", "-wal", "-shm"] {
+        let file = sibling_path(db_path, suffix);
+        if file.exists() {
+            let _ = fs::rename(&file, sibling_path(&file, ".corrupt"));
+        }
+    }
+}
+
+/// Copy `src` over `dest` atomically: temp file, fsync, rename.
+fn atomic_copy(src: &Path, dest: &Path) -> std::io::Result<()> {
+    let tmp = sibling_path(dest, ".tmp");
+    fs::copy(src, &tmp)?;
+    File::open(&tmp)?.sync_all()?;
+    fs::rename(&tmp, dest)?;
+    sync_parent_dir(dest);
+    Ok(())
+}
+
+/// Move a store's files (database, WAL, backups, legacy JSON) from `from` to
+/// `to`, unless `to` already holds a database. Returns how many were moved.
+/// Must run before either store is opened.
+pub(crate) fn relocate_store(from: &Path, to: &Path) -> std::io::Result<usize> {
+    let target_db = to.join(CACHE_DB_FILE);
+    if target_db.exists() {
+        return Ok(0);
+    }
+    let source_db = from.join(CACHE_DB_FILE);
+    let legacy = from.join(LEGACY_CACHE_FILE);
+    let mut files = vec![
+        source_db.clone(),
+        sibling_path(&source_db, "-wal"),
+        sibling_path(&source_db, "-shm"),
+        legacy.clone(),
+        migrated_path(&legacy),
+    ];
+    files.extend((0..BACKUP_GENERATIONS).map(|generation| backup_path(&source_db, generation)));
+
+    fs::create_dir_all(to)?;
+    let mut moved = 0;
+    for file in files.iter().filter(|file| file.is_file()) {
+        let name = file.file_name().expect("store files have a file name");
+        fs::rename(file, to.join(name))?;
+        moved += 1;
+    }
+    if moved > 0 {
+        sync_parent_dir(&target_db);
+    }
+    Ok(moved)
+}
+
+/// Snapshot the live database into a new backup generation, rotating older
+/// generations down and dropping the oldest.
+fn write_backup(conn: &Connection, db_path: &Path) -> Result<(), String> {
+    let tmp = sibling_path(db_path, ".bak.tmp");
+    let _ = fs::remove_file(&tmp);
+    conn.execute("VACUUM INTO ?1", params![tmp.to_string_lossy()])
+        .map_err(|e| format!("Failed to snapshot cache database: {e}"))?;
+    File::open(&tmp)
+        .and_then(|f| f.sync_all())
+        .map_err(|e| format!("Failed to sync cache backup: {e}"))?;
+    for generation in (1..BACKUP_GENERATIONS).rev() {
+        let older = backup_path(db_path, generation - 1);
+        if older.exists() {
+            fs::rename(&older, backup_path(db_path, generation))
+                .map_err(|e| format!("Failed to rotate cache backup: {e}"))?;
+        }
+    }
+    fs::rename(&tmp, backup_path(db_path, 0))
+        .map_err(|e| format!("Failed to install cache backup: {e}"))?;
+    sync_parent_dir(db_path);
+    Ok(())
+}
+
+fn backup_is_stale(db_path: &Path) -> bool {
+    fs::metadata(backup_path(db_path, 0))
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_none_or(|age| age > BACKUP_MAX_AGE)
+}
+
+/// Replace a database that failed to open or validate with the newest
+/// backup generation that passes validation. Starts empty if none does.
+fn recover_database(
+    db_path: &Path,
+    reason: &str,
+    log: &CacheLogger,
+) -> rusqlite::Result<Connection> {
+    quarantine_database(db_path);
+    for generation in 0..BACKUP_GENERATIONS {
+        let backup = backup_path(db_path, generation);
+        if !backup.is_file() {
+            continue;
+        }
+        if let Err(e) = atomic_copy(&backup, db_path) {
+            log(
+                "WARN",
+                &format!("cache recovery: failed to copy {}: {e}", backup.display()),
+            );
+            continue;
+        }
+        match open_validated(db_path) {
+            Ok(conn) => {
+                log(
+                    "WARN",
+                    &format!(
+                        "cache database failed validation ({reason}); restored backup generation {generation} from {}",
+                        backup.display()
+                    ),
+                );
+                return Ok(conn);
+            }
+            Err(e) => {
+                log(
+                    "WARN",
+                    &format!("cache recovery: backup generation {generation} invalid: {e}"),
+                );
+                let _ = fs::remove_file(db_path);
+            }
+        }
+    }
+    log(
+        "WARN",
+        &format!("cache database failed validation ({reason}); no valid backup, starting empty"),
+    );
+    open_database(db_path)
+}
+
+/// Import the legacy `persistent-cache.json` map into the database in a
+/// single transaction. Existing rows win over legacy ones. The JSON file is
+/// renamed afterwards (kept, not deleted) so the import runs only once.
+fn migrate_legacy_json(conn: &mut Connection, json_path: &Path, log: &CacheLogger) {
+    if !json_path.is_file() {
+        return;
+    }
+    let raw = match fs::read_to_string(json_path) {
+        Ok(raw) => raw,
+        Err(e) => {
+            log(
+                "WARN",
+                &format!(
+                    "cache migration: failed to read {}: {e}",
+                    json_path.display()
+                ),
+            );
+            return;
+        }
+    };
+    let entries = match serde_json::from_str::<Value>(&raw) {
+        Ok(Value::Object(map)) => map,
+        Ok(_) | Err(_) => {
+            log(
+                "WARN",
+                &format!(
+                    "cache migration: {} is not a JSON object, skipping import",
+                    json_path.display()
+                ),
+            );
+            Map::new()
+        }
+    };
+
+    let total = entries.len();
+    let result = (|| -> rusqlite::Result<usize> {
+        let tx = conn.transaction()?;
+        let mut imported = 0;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO cache_entries (key, value, updated_at) VALUES (?1, ?2, ?3)",
+            )?;
+            let now = now_ms();
+            for (key, value) in &entries {
+                let updated_at = envelope_updated_at(value).unwrap_or(now);
+                imported += stmt.execute(params![key, value.to_string(), updated_at])?;
+            }
+        }
+        tx.commit()?;
+        Ok(imported)
+    })();
+
+    match result {
+        Ok(imported) => {
+            let target = migrated_path(json_path);
+            if let Err(e) = fs::rename(json_path, &target) {
+                log(
+                    "WARN",
+                    &format!("cache migration: failed to rename legacy file: {e}"),
+                );
+            }
+            log(
+                "INFO",
+                &format!(
+                    "cache migration: imported {imported}/{total} entries from {}",
+                    json_path.display()
+                ),
+            );
+        }
+        Err(e) => log(
+            "ERROR",
+            &format!("cache migration failed, legacy file left in place: {e}"),
+        ),
+    }
+}
+
+/// `WMZ1 || zstd frame` when that is smaller than the JSON, else `None`.
+fn compress_json(json: &[u8]) -> Option<Vec<u8>> {
+    if json.len() < COMPRESS_MIN_BYTES {
+        return None;
+    }
+    let frame = zstd::bulk::compress(json, ZSTD_LEVEL).ok()?;
+    if frame.len() + COMPRESSED_MAGIC.len() >= json.len() {
+        return None;
+    }
+    let mut out = Vec::with_capacity(COMPRESSED_MAGIC.len() + frame.len());
+    out.extend_from_slice(COMPRESSED_MAGIC);
+    out.extend_from_slice(&frame);
+    Some(out)
+}
+
+/// Inverse of [`compress_json`]; payloads without the magic header are
+/// legacy plain JSON and are returned as-is.
+fn decompress_payload(payload: Vec<u8>) -> Result<Vec<u8>, String> {
+    let Some(frame) = payload.strip_prefix(COMPRESSED_MAGIC) else {
+        return Ok(payload);
+    };
+    let mut json = Vec::new();
+    zstd::stream::read::Decoder::new(frame)
+        .and_then(|decoder| decoder.take(MAX_VALUE_BYTES + 1).read_to_end(&mut json))
+        .map_err(|e| format!("corrupt compressed value: {e}"))?;
+    if json.len() as u64 > MAX_VALUE_BYTES {
+        return Err("compressed value exceeds size limit".to_string());
+    }
+    Ok(json)
+}
+
+/// On-disk form of a value: compressed when worthwhile, then sealed when a
+/// cipher is configured. Small unencrypted values stay readable JSON text.
+fn encode_value(cipher: Option<&CacheCipher>, key: &str, value: &Value) -> SqlValue {
+    let json = value.to_string();
+    let compressed = compress_json(json.as_bytes());
+    match (cipher, compressed) {
+        (Some(cipher), Some(payload)) => SqlValue::Blob(cipher.seal(key, &payload)),
+        (Some(cipher), None) => SqlValue::Blob(cipher.seal(key, json.as_bytes())),
+        (None, Some(payload)) => SqlValue::Blob(payload),
+        (None, None) => SqlValue::Text(json),
+    }
+}
+
+fn upsert_entry(
+    conn: &Connection,
+    cipher: Option<&CacheCipher>,
+    key: &str,
+    value: &Value,
+) -> rusqlite::Result<usize> {
+    let updated_at = envelope_updated_at(value).unwrap_or_else(now_ms);
+    conn.execute(
+        "INSERT INTO cache_entries (key, value, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+        params![key, encode_value(cipher, key, value), updated_at],
+    )
+}
+
+/// Record `value` as a version of `key` and drop versions the rule no
+/// longer covers. A rewrite with an unchanged `updatedAt` replaces the row.
+fn record_history(
+    conn: &Connection,
+    cipher: Option<&CacheCipher>,
+    rule: HistoryRule,
+    key: &str,
+    value: &Value,
+    now: i64,
+) -> rusqlite::Result<()> {
+    let updated_at = envelope_updated_at(value).unwrap_or(now);
+    conn.execute(
+        "INSERT INTO cache_history (key, updated_at, value) VALUES (?1, ?2, ?3)
+         ON CONFLICT(key, updated_at) DO UPDATE SET value = excluded.value",
+        params![key, updated_at, encode_value(cipher, key, value)],
+    )?;
+    if let Some(max_age) = rule.max_age {
+        conn.execute(
+            "DELETE FROM cache_history WHERE key = ?1 AND updated_at < ?2",
+            params![key, now - max_age.as_millis() as i64],
+        )?;
+    }
+    if let Some(max_versions) = rule.max_versions {
+        conn.execute(
+            "DELETE FROM cache_history WHERE key = ?1 AND updated_at NOT IN (
+                 SELECT updated_at FROM cache_history WHERE key = ?1
+                 ORDER BY updated_at DESC LIMIT ?2
+             )",
+            params![key, max_versions as i64],
+        )?;
+    }
+    Ok(())
+}
+
+/// Age out versions of keys that went quiet since their last write, drop
+/// the history of keys no rule covers any more (after a policy change), then
+/// trim the oldest versions of any key until history fits its byte quota.
+fn prune_history(conn: &Connection, policy: &CachePolicy, now: i64) -> rusqlite::Result<()> {
+    let keys = conn
+        .prepare("SELECT DISTINCT key FROM cache_history")?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for key in keys {
+        let cutoff = match policy.history_for(&key) {
+            Some(HistoryRule {
+                max_age: Some(max_age),
+                ..
+            }) => now - max_age.as_millis() as i64,
+            Some(_) => continue,
+            None => i64::MAX,
+        };
+        conn.execute(
+            "DELETE FROM cache_history WHERE key = ?1 AND updated_at < ?2",
+            params![key, cutoff],
+        )?;
+    }
+    conn.execute(
+        "DELETE FROM cache_history WHERE rowid IN (
+             SELECT rowid FROM (
+                 SELECT rowid, SUM(length(CAST(value AS BLOB)))
+                     OVER (ORDER BY updated_at DESC, key) AS newer_bytes
+                 FROM cache_history
+             ) WHERE newer_bytes > ?1
+         )",
+        params![policy.history_max_bytes as i64],
+    )?;
+    Ok(())
+}
+
+/// Re-encrypt rows that are still plaintext, then rewrite the file and drop
+/// artifacts (old backups, the migrated legacy JSON) that hold plaintext.
+fn encrypt_plaintext_entries(
+    conn: &mut Connection,
+    cipher: &CacheCipher,
+    data: &Map<String, Value>,
+    keys: &[String],
+    dir: &Path,
+    db_path: Option<&Path>,
+) -> Result<(), String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to encrypt cache entries: {e}"))?;
+    for key in keys {
+        if let Some(value) = data.get(key) {
+            upsert_entry(&tx, Some(cipher), key, value)
+                .map_err(|e| format!("Failed to encrypt cache entry '{key}': {e}"))?;
+        }
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to encrypt cache entries: {e}"))?;
+    conn.execute_batch("VACUUM")
+        .map_err(|e| format!("Failed to compact cache database after encryption: {e}"))?;
+
+    let _ = fs::remove_file(migrated_path(&dir.join(LEGACY_CACHE_FILE)));
+    if let Some(db_path) = db_path {
+        conn.pragma_update(None, "wal_checkpoint", "TRUNCATE")
+            .map_err(|e| format!("Failed to checkpoint cache database: {e}"))?;
+        for generation in 0..BACKUP_GENERATIONS {
+            let _ = fs::remove_file(backup_path(db_path, generation));
+        }
+    }
+    Ok(())
+}
+
+/// One-line summary of an eviction pass for the desktop log.
+pub(crate) fn format_eviction(trigger: &str, stats: &EvictionStats) -> String {
+    format!(
+        "cache eviction ({trigger}): expired={} evicted={} freed={}B remaining={} ({}B)",
+        stats.expired, stats.evicted, stats.freed_bytes, stats.remaining, stats.remaining_bytes
+    )
+}
+
+/// Rows read back from disk, sorted by what has to happen to them next.
+#[derive(Default)]
+struct LoadedEntries {
+    data: Map<String, Value>,
+    /// Stored as JSON text while a cipher is configured; re-encrypted on open.
+    plaintext: Vec<String>,
+    /// Sealed with a key we do not have.
+    undecryptable: Vec<String>,
+    unparseable: usize,
+}
+
+enum DecodeError {
+    Undecryptable,
+    Unparseable,
+}
+
+/// Inverse of [`encode_value`]. Also reports whether the row was sealed, so
+/// callers can spot plaintext left over from before encryption.
+fn decode_value(
+    cipher: Option<&CacheCipher>,
+    key: &str,
+    raw: SqlValue,
+) -> Result<(Value, bool), DecodeError> {
+    let (payload, sealed) = match raw {
+        SqlValue::Blob(bytes) if cache_crypto::is_sealed(&bytes) => {
+            match cipher.map(|cipher| cipher.open(key, &bytes)) {
+                Some(Ok(payload)) => (payload, true),
+                Some(Err(_)) | None => return Err(DecodeError::Undecryptable),
+            }
+        }
+        SqlValue::Blob(bytes) => (bytes, false),
+        SqlValue::Text(text) => (text.into_bytes(), false),
+        _ => return Err(DecodeError::Unparseable),
+    };
+    decompress_payload(payload)
+        .ok()
+        .and_then(|json| serde_json::from_slice::<Value>(&json).ok())
+        .map(|value| (value, sealed))
+        .ok_or(DecodeError::Unparseable)
+}
+
+fn load_entries(
+    conn: &Connection,
+    cipher: Option<&CacheCipher>,
+) -> rusqlite::Result<LoadedEntries> {
+    let mut stmt = conn.prepare("SELECT key, value FROM cache_entries")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, SqlValue>(1)?))
+    })?;
+    let mut loaded = LoadedEntries::default();
+    for row in rows {
+        let (key, raw) = row?;
+        match decode_value(cipher, &key, raw) {
+            Ok((value, sealed)) => {
+                if cipher.is_some() && !sealed {
+                    loaded.plaintext.push(key.clone());
+                }
+                loaded.data.insert(key, value);
+            }
+            Err(DecodeError::Undecryptable) => loaded.undecryptable.push(key),
+            Err(DecodeError::Unparseable) => loaded.unparseable += 1,
+        }
+    }
+    Ok(loaded)
+}
+
+impl PersistentCache {
+    /// [`open_with_policy`](Self::open_with_policy) using the policy file in
+    /// `dir`.
+    #[cfg(test)]
+    pub(crate) fn open(dir: &Path, cipher: Option<CacheCipher>, log: CacheLogger) -> Self {
+        let policy = CachePolicy::load(dir, &log);
+        Self::open_with_policy(dir, policy, cipher, log)
+    }
+
+    /// Open (or create) the cache database in `dir` and load it into memory,
+    /// importing the legacy JSON file on first run. If the database cannot be
+    /// opened the cache runs in memory only, matching the old behaviour of
+    /// starting empty when the JSON file was unreadable. The policy is loaded
+    /// by the caller so every namespace shares one `cache-policy.json`.
+    pub(crate) fn open_with_policy(
+        dir: &Path,
+        policy: CachePolicy,
+        cipher: Option<CacheCipher>,
+        log: CacheLogger,
+    ) -> Self {
+        let db_path = dir.join(CACHE_DB_FILE);
+        let opened = match open_validated(&db_path) {
+            Ok(conn) => Ok(conn),
+            Err(reason) => recover_database(&db_path, &reason, &log),
+        };
+        let (mut conn, path) = match opened {
+            Ok(conn) => (conn, Some(db_path)),
+            Err(e) => {
+                log(
+                    "ERROR",
+                    &format!(
+                        "cache database {} unavailable, caching in memory only: {e}",
+                        db_path.display()
+                    ),
+                );
+                (open_in_memory(), None)
+            }
+        };
+
+        // An in-memory import would be dropped at exit while the rename
+        // marks it done, so leave the legacy file for a later run.
+        let legacy = dir.join(LEGACY_CACHE_FILE);
+        if path.is_some() {
+            migrate_legacy_json(&mut conn, &legacy, &log);
+        } else if legacy.is_file() {
+            log(
+                "WARN",
+                &format!(
+                    "cache migration: skipped while caching in memory; {} left in place",
+                    legacy.display()
+                ),
+            );
+        }
+
+        let loaded = match load_entries(&conn, cipher.as_ref()) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                log("ERROR", &format!("cache load failed: {e}"));
+                LoadedEntries::default()
+            }
+        };
+        if loaded.unparseable > 0 {
+            log(
+                "WARN",
+                &format!(
+                    "cache load skipped {} unparseable entries",
+                    loaded.unparseable
+                ),
+            );
+        }
+        if !loaded.undecryptable.is_empty() {
+            // Without the key these rows are noise; drop them so they do not
+            // count against the quota or shadow fresh writes.
+            log(
+                "ERROR",
+                &format!(
+                    "cache encryption key missing from the keychain or replaced: discarding {} entries that cannot be decrypted",
+                    loaded.undecryptable.len()
+                ),
+            );
+            for key in &loaded.undecryptable {
+                let _ = conn.execute("DELETE FROM cache_entries WHERE key = ?1", params![key]);
+                let _ = conn.execute("DELETE FROM cache_history WHERE key = ?1", params![key]);
+            }
+        }
+        if let (Some(cipher), false) = (cipher.as_ref(), loaded.plaintext.is_empty()) {
+            match encrypt_plaintext_entries(
+                &mut conn,
+                cipher,
+                &loaded.data,
+                &loaded.plaintext,
+                dir,
+                path.as_deref(),
+            ) {
+                Ok(()) => log(
+                    "INFO",
+                    &format!(
+                        "cache: encrypted {} plaintext entries at rest",
+                        loaded.plaintext.len()
+                    ),
+                ),
+                Err(e) => log("ERROR", &e),
+            }
+        }
+        let data = loaded.data;
+
+        if let Some(path) = path.as_deref() {
+            if backup_is_stale(path) {
+                if let Err(e) = write_backup(&conn, path) {
+                    log("WARN", &e);
+                }
+            }
+        }
+
+        let sizes = data
+            .iter()
+            .map(|(key, value)| (key.clone(), json_size(value)))
+            .collect();
+        let cache = PersistentCache {
+            data: Mutex::new(data),
+            dirty: Mutex::new(HashSet::new()),
+            dirty_signal: Condvar::new(),
+            sizes: Mutex::new(sizes),
+            db: Mutex::new(conn),
+            flush_stats: Mutex::new(FlushStats::default()),
+            path,
+            accessed: Mutex::new(HashMap::new()),
+            policy,
+            cipher,
+            listener: OnceLock::new(),
+            refused_writes: AtomicU64::new(0),
+            exit_backup_taken: AtomicBool::new(false),
+        };
+        match cache.evict() {
+            Ok(stats) => log("INFO", &format_eviction("startup", &stats)),
+            Err(e) => log("WARN", &e),
+        }
+        cache
+    }
+
+    /// Cache with no backing file, used when the app data dir is unusable.
+    pub(crate) fn in_memory() -> Self {
+        PersistentCache {
+            data: Mutex::new(Map::new()),
+            dirty: Mutex::new(HashSet::new()),
+            dirty_signal: Condvar::new(),
+            sizes: Mutex::new(HashMap::new()),
+            db: Mutex::new(open_in_memory()),
+            flush_stats: Mutex::new(FlushStats::default()),
+            path: None,
+            accessed: Mutex::new(HashMap::new()),
+            policy: CachePolicy::default(),
+            cipher: None,
+            listener: OnceLock::new(),
+            refused_writes: AtomicU64::new(0),
+            exit_backup_taken: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<Value> {
+        let data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        let value = data.get(key).cloned();
+        if value.is_some() {
+            let mut accessed = self.accessed.lock().unwrap_or_else(|e| e.into_inner());
+            accessed.insert(key.to_string(), now_ms());
+        }
+        value
+    }
+
+    /// Values for every key in `keys` that is present, cloned under a single
+    /// lock acquisition. Missing keys are simply absent from the result.
+    pub(crate) fn get_many(&self, keys: &[String]) -> Map<String, Value> {
+        let data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        let found: Map<String, Value> = keys
+            .iter()
+            .filter_map(|key| data.get(key).map(|value| (key.clone(), value.clone())))
+            .collect();
+        drop(data);
+        if !found.is_empty() {
+            let now = now_ms();
+            let mut accessed = self.accessed.lock().unwrap_or_else(|e| e.into_inner());
+            for key in found.keys() {
+                accessed.insert(key.clone(), now);
+            }
+        }
+        found
+    }
+
+    /// Admission check for a webview write of `json_len` bytes to `key`.
+    /// `batch` holds the sizes of entries admitted alongside it, which count
+    /// toward the prefix caps in place of their stored values.
+    pub(crate) fn check_write(
+        &self,
+        key: &str,
+        json_len: usize,
+        batch: &HashMap<String, u64>,
+    ) -> Result<(), CacheWriteRefusal> {
+        let sizes = self.sizes.lock().unwrap_or_else(|e| e.into_inner());
+        self.admit(&sizes, key, json_len, batch)
+    }
+
+    /// [`Self::check_write`] against an already locked size index.
+    fn admit(
+        &self,
+        sizes: &HashMap<String, u64>,
+        key: &str,
+        json_len: usize,
+        batch: &HashMap<String, u64>,
+    ) -> Result<(), CacheWriteRefusal> {
+        self.policy.limits.check(key, json_len)?;
+        self.policy.limits.check_total(key, json_len, |prefix| {
+            let stored: u64 = sizes
+                .iter()
+                .filter(|(k, _)| k.starts_with(prefix) && *k != key && !batch.contains_key(*k))
+                .map(|(_, size)| size)
+                .sum();
+            let queued: u64 = batch
+                .iter()
+                .filter(|(k, _)| k.starts_with(prefix) && *k != key)
+                .map(|(_, size)| size)
+                .sum();
+            stored + queued
+        })
+    }
+
+    /// Count refused writes; returns the total since launch.
+    pub(crate) fn record_refusals(&self, refusals: &[CacheWriteRefusal]) -> u64 {
+        let added = refusals.len() as u64;
+        self.refused_writes.fetch_add(added, Ordering::Relaxed) + added
+    }
+
+    /// Update the mirror and queue the key for the next flush. Returns
+    /// without touching the disk.
+    pub(crate) fn put(&self, key: String, value: Value) -> Result<(), String> {
+        self.put_many(vec![(key, value)])
+    }
+
+    /// Queue several entries under one lock acquisition; the next flush
+    /// writes them in a single transaction.
+    pub(crate) fn put_many(&self, entries: Vec<(String, Value)>) -> Result<(), String> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let mut changes = Vec::with_capacity(entries.len());
+        let mut data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        let mut dirty = self.dirty.lock().unwrap_or_else(|e| e.into_inner());
+        let mut sizes = self.sizes.lock().unwrap_or_else(|e| e.into_inner());
+        for (key, value) in entries {
+            changes.push(CacheChange {
+                key: key.clone(),
+                updated_at: envelope_updated_at(&value),
+                deleted: false,
+            });
+            dirty.insert(key.clone());
+            sizes.insert(key.clone(), json_size(&value));
+            data.insert(key, value);
+        }
+        self.dirty_signal.notify_all();
+        drop((sizes, dirty, data));
+        self.notify(&changes);
+        Ok(())
+    }
+
+    pub(crate) fn remove(&self, key: &str) -> Result<(), String> {
+        let mut data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        let mut dirty = self.dirty.lock().unwrap_or_else(|e| e.into_inner());
+        let existed = data.remove(key).is_some();
+        dirty.insert(key.to_string());
+        self.sizes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+        self.dirty_signal.notify_all();
+        drop((dirty, data));
+        let mut accessed = self.accessed.lock().unwrap_or_else(|e| e.into_inner());
+        accessed.remove(key);
+        drop(accessed);
+        if existed {
+            self.notify(&[CacheChange::deleted(key.to_string())]);
+        }
+        Ok(())
+    }
+
+    /// Copy of every entry, for export.
+    pub(crate) fn snapshot(&self) -> Map<String, Value> {
+        self.data.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Copy of the entries whose key starts with `prefix`.
+    pub(crate) fn snapshot_prefix(&self, prefix: &str) -> Map<String, Value> {
+        let data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        data.iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Apply already-validated snapshot entries in one step under the mirror
+    /// lock; they reach disk with the next flush like any other write. Each
+    /// entry passes the same admission check as a webview write, counting the
+    /// entries imported before it; refused entries leave the local copy alone.
+    pub(crate) fn import(&self, entries: Map<String, Value>, mode: ImportMode) -> ImportReport {
+        let mut report = ImportReport::default();
+        let mut changes = Vec::new();
+        let mut data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        let mut dirty = self.dirty.lock().unwrap_or_else(|e| e.into_inner());
+        let mut sizes = self.sizes.lock().unwrap_or_else(|e| e.into_inner());
+
+        if mode == ImportMode::Replace {
+            let stale: Vec<String> = data
+                .keys()
+                .filter(|key| !entries.contains_key(*key))
+                .cloned()
+                .collect();
+            for key in stale {
+                data.remove(&key);
+                sizes.remove(&key);
+                dirty.insert(key.clone());
+                changes.push(CacheChange::deleted(key));
+                report.removed += 1;
+            }
+        }
+
+        for (key, value) in entries {
+            let incoming = envelope_updated_at(&value);
+            if mode == ImportMode::Merge {
+                let local = data.get(&key).and_then(envelope_updated_at);
+                if let (Some(local), Some(incoming)) = (local, incoming) {
+                    if local > incoming {
+                        report.kept_newer += 1;
+                        continue;
+                    }
+                }
+            }
+            let size = json_size(&value);
+            if let Err(refusal) = self.admit(&sizes, &key, size as usize, &HashMap::new()) {
+                report.refused.push(refusal);
+                continue;
+            }
+            changes.push(CacheChange {
+                key: key.clone(),
+                updated_at: incoming,
+                deleted: false,
+            });
+            dirty.insert(key.clone());
+            sizes.insert(key.clone(), size);
+            data.insert(key, value);
+            report.imported += 1;
+        }
+
+        self.dirty_signal.notify_all();
+        drop((sizes, dirty, data));
+        self.notify(&changes);
+        report
+    }
+
+    /// Install the change listener. Only the first call takes effect.
+    pub(crate) fn set_change_listener(&self, listener: CacheChangeListener) {
+        let _ = self.listener.set(listener);
+    }
+
+    /// Always called with no cache locks held, so listeners may read back.
+    fn notify(&self, changes: &[CacheChange]) {
+        if let Some(listener) = self.listener.get() {
+            for change in changes {
+                listener(change);
+            }
+        }
+    }
+
+    /// Block until at least one key is waiting to be flushed.
+    pub(crate) fn wait_for_dirty(&self) {
+        let mut dirty = self.dirty.lock().unwrap_or_else(|e| e.into_inner());
+        while dirty.is_empty() {
+            dirty = self
+                .dirty_signal
+                .wait(dirty)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Write every dirty key to disk in one transaction and record how long
+    /// it took.
+    pub(crate) fn flush_pending(&self) -> Result<FlushReport, String> {
+        let mut db = self.db.lock().unwrap_or_else(|e| e.into_inner());
+        let started = Instant::now();
+        let entries = self.write_dirty(&mut db)?;
+        let elapsed = started.elapsed();
+        if entries > 0 {
+            let mut stats = self.flush_stats.lock().unwrap_or_else(|e| e.into_inner());
+            stats.last_ms = elapsed.as_millis() as u64;
+            stats.max_ms = stats.max_ms.max(stats.last_ms);
+        }
+        Ok(FlushReport { entries, elapsed })
+    }
+
+    /// Caller holds the `db` lock. On failure the keys are re-queued so the
+    /// next flush retries them.
+    fn write_dirty(&self, db: &mut Connection) -> Result<usize, String> {
+        let batch: Vec<(String, Option<Value>)> = {
+            let data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+            let mut dirty = self.dirty.lock().unwrap_or_else(|e| e.into_inner());
+            dirty
+                .drain()
+                .map(|key| {
+                    let value = data.get(&key).cloned();
+                    (key, value)
+                })
+                .collect()
+        };
+        if batch.is_empty() {
+            return Ok(0);
+        }
+
+        let now = now_ms();
+        let written = db.transaction().and_then(|tx| {
+            for (key, value) in &batch {
+                match value {
+                    Some(value) => {
+                        upsert_entry(&tx, self.cipher.as_ref(), key, value)?;
+                        if let Some(rule) = self.policy.history_for(key) {
+                            record_history(&tx, self.cipher.as_ref(), rule, key, value, now)?;
+                        }
+                    }
+                    None => {
+                        tx.execute("DELETE FROM cache_entries WHERE key = ?1", params![key])?;
+                        tx.execute("DELETE FROM cache_history WHERE key = ?1", params![key])?;
+                    }
+                }
+            }
+            tx.commit()
+        });
+        if let Err(e) = written {
+            let mut dirty = self.dirty.lock().unwrap_or_else(|e| e.into_inner());
+            dirty.extend(batch.into_iter().map(|(key, _)| key));
+            return Err(format!("Failed to flush cache entries: {e}"));
+        }
+        Ok(batch.len())
+    }
+
+    /// Retained versions of `key` with `updatedAt >= since`, oldest first.
+    /// Only versions that reached disk are kept, so writes coalesced within
+    /// one flush window count once. Empty for keys without a history rule.
+    pub(crate) fn history(
+        &self,
+        key: &str,
+        since: Option<i64>,
+    ) -> Result<Vec<CacheHistoryEntry>, String> {
+        let mut db = self.db.lock().unwrap_or_else(|e| e.into_inner());
+        self.write_dirty(&mut db)?;
+        let mut stmt = db
+            .prepare(
+                "SELECT updated_at, value FROM cache_history
+                 WHERE key = ?1 AND updated_at >= ?2 ORDER BY updated_at",
+            )
+            .map_err(|e| format!("Failed to read cache history: {e}"))?;
+        let rows = stmt
+            .query_map(params![key, since.unwrap_or(i64::MIN)], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, SqlValue>(1)?))
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| format!("Failed to read cache history: {e}"))?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(updated_at, raw)| {
+                decode_value(self.cipher.as_ref(), key, raw)
+                    .ok()
+                    .map(|(value, _)| CacheHistoryEntry { updated_at, value })
+            })
+            .collect())
+    }
+
+    /// Keys with their stored size and `updatedAt`, sorted by key.
+    pub(crate) fn list(&self) -> Result<Vec<CacheEntryInfo>, String> {
+        let mut db = self.db.lock().unwrap_or_else(|e| e.into_inner());
+        self.write_dirty(&mut db)?;
+        let mut stmt = db
+            .prepare("SELECT key, length(CAST(value AS BLOB)), updated_at FROM cache_entries ORDER BY key")
+            .map_err(|e| format!("Failed to list cache entries: {e}"))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(CacheEntryInfo {
+                    key: row.get(0)?,
+                    size_bytes: row.get::<_, i64>(1)?.max(0) as u64,
+                    updated_at: row.get(2)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| format!("Failed to list cache entries: {e}"))?;
+        Ok(rows)
+    }
+
+    pub(crate) fn stats(&self) -> Result<CacheStats, String> {
+        let mut db = self.db.lock().unwrap_or_else(|e| e.into_inner());
+        self.write_dirty(&mut db)?;
+        let (entry_count, total_bytes) = db
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(length(CAST(value AS BLOB))), 0) FROM cache_entries",
+                [],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+            )
+            .map_err(|e| format!("Failed to read cache stats: {e}"))?;
+        let flush = *self.flush_stats.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(CacheStats {
+            entry_count: entry_count.max(0) as usize,
+            total_bytes: total_bytes.max(0) as u64,
+            max_bytes: self.policy.max_bytes,
+            last_flush_ms: flush.last_ms,
+            max_flush_ms: flush.max_ms,
+            encrypted: self.cipher.is_some(),
+            refused_writes: self.refused_writes.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Delete every entry whose key starts with `prefix`; returns how many
+    /// were removed. An empty prefix is refused rather than clearing the
+    /// whole cache.
+    pub(crate) fn remove_prefix(&self, prefix: &str) -> Result<usize, String> {
+        if prefix.is_empty() {
+            return Err("Cache prefix must not be empty".to_string());
+        }
+        let mut data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+        let mut dirty = self.dirty.lock().unwrap_or_else(|e| e.into_inner());
+        let doomed: Vec<String> = data
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        let mut sizes = self.sizes.lock().unwrap_or_else(|e| e.into_inner());
+        for key in &doomed {
+            data.remove(key);
+            sizes.remove(key);
+        }
+        drop(sizes);
+        let removed = doomed.len();
+        dirty.extend(doomed.iter().cloned());
+        self.dirty_signal.notify_all();
+        drop((dirty, data));
+        let mut accessed = self.accessed.lock().unwrap_or_else(|e| e.into_inner());
+        accessed.retain(|key, _| !key.starts_with(prefix));
+        drop(accessed);
+        let changes: Vec<CacheChange> = doomed.into_iter().map(CacheChange::deleted).collect();
+        self.notify(&changes);
+        Ok(removed)
+    }
+
+    /// Persist pending access times, then drop expired entries and, if the
+    /// cache is over quota, the least-recently-used ones.
+    pub(crate) fn evict(&self) -> Result<EvictionStats, String> {
+        self.evict_at(now_ms())
+    }
+
+    fn evict_at(&self, now: i64) -> Result<EvictionStats, String> {
+        let mut db = self.db.lock().unwrap_or_else(|e| e.into_inner());
+        self.write_dirty(&mut db)?;
+        let pending = std::mem::take(&mut *self.accessed.lock().unwrap_or_else(|e| e.into_inner()));
+
+        let tx = db
+            .transaction()
+            .map_err(|e| format!("Failed to start cache eviction: {e}"))?;
+        {
+            let mut touch = tx
+                .prepare(
+                    "UPDATE cache_entries SET accessed_at = ?2 WHERE key = ?1 AND accessed_at < ?2",
+                )
+                .map_err(|e| format!("Failed to record cache access times: {e}"))?;
+            for (key, at) in &pending {
+                touch
+                    .execute(params![key, at])
+                    .map_err(|e| format!("Failed to record cache access times: {e}"))?;
+            }
+        }
+
+        let entries = {
+            let mut stmt = tx
+                .prepare("SELECT key, updated_at, accessed_at, length(CAST(value AS BLOB)) FROM cache_entries")
+                .map_err(|e| format!("Failed to scan cache entries: {e}"))?;
+            let rows = stmt
+                .query_map([], |row| {
+                    let updated_at: i64 = row.get(1)?;
+                    let accessed_at: i64 = row.get(2)?;
+                    Ok(EntryUsage {
+                        key: row.get(0)?,
+                        updated_at,
+                        last_used: updated_at.max(accessed_at),
+                        size: row.get::<_, i64>(3)?.max(0) as u64,
+                    })
+                })
+                .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+                .map_err(|e| format!("Failed to scan cache entries: {e}"))?;
+            rows
+        };
+
+        let (doomed, stats) = plan_eviction(&self.policy, entries, now);
+        {
+            let mut delete = tx
+                .prepare("DELETE FROM cache_entries WHERE key = ?1")
+                .map_err(|e| format!("Failed to evict cache entries: {e}"))?;
+            let mut delete_history = tx
+                .prepare("DELETE FROM cache_history WHERE key = ?1")
+                .map_err(|e| format!("Failed to evict cache entries: {e}"))?;
+            for key in &doomed {
+                delete
+                    .execute(params![key])
+                    .map_err(|e| format!("Failed to evict cache entries: {e}"))?;
+                delete_history
+                    .execute(params![key])
+                    .map_err(|e| format!("Failed to evict cache entries: {e}"))?;
+            }
+        }
+        prune_history(&tx, &self.policy, now)
+            .map_err(|e| format!("Failed to prune cache history: {e}"))?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit cache eviction: {e}"))?;
+
+        drop(db);
+        if !doomed.is_empty() {
+            // A key rewritten since `write_dirty` above holds a fresh value;
+            // keep it in the mirror and let the next flush restore the row.
+            let mut data = self.data.lock().unwrap_or_else(|e| e.into_inner());
+            let dirty = self.dirty.lock().unwrap_or_else(|e| e.into_inner());
+            let mut sizes = self.sizes.lock().unwrap_or_else(|e| e.into_inner());
+            let evicted: Vec<CacheChange> = doomed
+                .into_iter()
+                .filter(|key| !dirty.contains(key) && data.remove(key).is_some())
+                .inspect(|key| {
+                    sizes.remove(key);
+                })
+                .map(CacheChange::deleted)
+                .collect();
+            drop((sizes, dirty, data));
+            self.notify(&evicted);
+        }
+        Ok(stats)
+    }
+
+    /// Write pending entries, fold the write-ahead log back into the main
+    /// database file and, the first time only, take a fresh backup
+    /// generation. Called on exit.
+    pub(crate) fn flush(&self) -> Result<FlushReport, String> {
+        let report = self.flush_pending()?;
+        let db = self.db.lock().unwrap_or_else(|e| e.into_inner());
+        db.pragma_update(None, "wal_checkpoint", "TRUNCATE")
+            .map_err(|e| format!("Failed to checkpoint cache database: {e}"))?;
+        if let Some(path) = self.path.as_deref() {
+            if !self.exit_backup_taken.load(Ordering::SeqCst) {
+                write_backup(&db, path)?;
+                self.exit_backup_taken.store(true, Ordering::SeqCst);
+            }
+        }
+        Ok(report)
+    }
+}
+
+impl Drop for PersistentCache {
+    fn drop(&mut self) {
+        let _ = self.flush_pending();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use serde_json::json;
+
+    fn quiet() -> CacheLogger {
+        Box::new(|_, _| {})
+    }
+
+    fn capture() -> (CacheLogger, std::sync::Arc<Mutex<Vec<String>>>) {
+        let lines = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        let log: CacheLogger = Box::new(move |level, message| {
+            sink.lock().unwrap().push(format!("{level} {message}"));
+        });
+        (log, lines)
+    }
+
+    #[test]
+    fn entries_survive_reopen() {
+        let dir = temp_dir("reopen");
+        {
+            let cache = PersistentCache::open(&dir, None, quiet());
+            cache
+                .put(
+                    "a".into(),
+                    json!({"key": "a", "updatedAt": 1, "data": [1, 2]}),
+                )
+                .unwrap();
+            cache
+                .put(
+                    "b".into(),
+                    json!({"key": "b", "updatedAt": 2, "data": null}),
+                )
+                .unwrap();
+            cache.remove("b").unwrap();
+            cache.flush().unwrap();
+        }
+        let cache = PersistentCache::open(&dir, None, quiet());
+        assert_eq!(
+            cache.get("a"),
+            Some(json!({"key": "a", "updatedAt": 1, "data": [1, 2]}))
+        );
+        assert_eq!(cache.get("b"), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn flush_rotates_backup_generations() {
+        let dir = temp_dir("rotate");
+        let db_path = dir.join(CACHE_DB_FILE);
+        let cache = PersistentCache::open(&dir, None, quiet());
+        assert!(backup_path(&db_path, 0).exists(), "startup backup missing");
+        assert!(!backup_path(&db_path, 1).exists());
+
+        cache.put("k".into(), json!(1)).unwrap();
+        cache.flush().unwrap();
+        assert!(backup_path(&db_path, 0).exists());
+        assert!(backup_path(&db_path, 1).exists());
+        assert!(!sibling_path(&db_path, ".bak.tmp").exists());
+
+        // A second exit event must not rotate the pre-session backup out.
+        let before_session = fs::read(backup_path(&db_path, 1)).unwrap();
+        cache.flush().unwrap();
+        assert_eq!(fs::read(backup_path(&db_path, 1)).unwrap(), before_session);
+        assert_ne!(fs::read(backup_path(&db_path, 0)).unwrap(), before_session);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn restores_last_good_generation_when_database_is_corrupt() {
+        let dir = temp_dir("recover");
+        let db_path = dir.join(CACHE_DB_FILE);
+        {
+            let cache = PersistentCache::open(&dir, None, quiet());
+            cache
+                .put("kept".into(), json!({"updatedAt": 1, "data": "ok"}))
+                .unwrap();
+            cache.flush().unwrap();
+        }
+        fs::write(&db_path, b"truncated garbage that is not sqlite").unwrap();
+
+        let (log, lines) = capture();
+        let cache = PersistentCache::open(&dir, None, log);
+        assert_eq!(
+            cache.get("kept"),
+            Some(json!({"updatedAt": 1, "data": "ok"}))
+        );
+        assert!(sibling_path(&db_path, ".corrupt").exists());
+        let lines = lines.lock().unwrap();
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.starts_with("WARN") && l.contains("restored backup generation 0")),
+            "{lines:?}"
+        );
+        drop(lines);
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn usage(key: &str, updated_at: i64, last_used: i64, size: u64) -> EntryUsage {
+        EntryUsage {
+            key: key.into(),
+            updated_at,
+            last_used,
+            size,
+        }
+    }
+
+    #[test]
+    fn eviction_plan_expires_by_longest_prefix_then_trims_lru() {
+        let policy = CachePolicy {
+            ttls: vec![
+                ("feed:".into(), Duration::from_secs(10)),
+                ("feed:pinned".into(), Duration::from_secs(1000)),
+            ],
+            max_bytes: 250,
+            history: Vec::new(),
+            history_max_bytes: DEFAULT_HISTORY_MAX_BYTES,
+            limits: WriteLimits::default(),
+        };
+        let now = 100_000;
+        let entries = vec![
+            usage("feed:world", 50_000, 50_000, 100),
+            usage("feed:pinned:a", 50_000, 50_000, 100),
+            usage("summary:old", 1_000, 1_000, 100),
+            usage("summary:read", 2_000, 99_000, 100),
+        ];
+        let (mut doomed, stats) = plan_eviction(&policy, entries, now);
+        doomed.sort();
+        assert_eq!(
+            doomed,
+            vec!["feed:world".to_string(), "summary:old".to_string()]
+        );
+        assert_eq!(stats.expired, 1);
+        assert_eq!(stats.evicted, 1);
+        assert_eq!(stats.freed_bytes, 200);
+        assert_eq!(stats.remaining, 2);
+        assert_eq!(stats.remaining_bytes, 200);
+    }
+
+    #[test]
+    fn evict_uses_recorded_reads_and_policy_overrides() {
+        let dir = temp_dir("evict");
+        fs::write(
+            dir.join(CACHE_POLICY_FILE),
+            r#"{"maxBytes": 40, "ttlSeconds": {"feed:": 0}, "allowedKeyPrefixes": ["panel:"], "entryLimits": {"panel:": 10}}"#,
+        )
+        .unwrap();
+        let cache = PersistentCache::open(&dir, None, quiet());
+        assert!(cache.policy.ttl_for("feed:world").is_none());
+        let none = HashMap::new();
+        assert!(cache.check_write("panel:a", 10, &none).is_ok());
+        assert!(cache.check_write("panel:a", 11, &none).is_err());
+        assert!(cache.check_write("feed:world", 11, &none).is_ok());
+        cache
+            .put("a".into(), json!({"updatedAt": 1, "data": "aaaa"}))
+            .unwrap();
+        cache
+            .put("b".into(), json!({"updatedAt": 2, "data": "bbbb"}))
+            .unwrap();
+        assert!(cache.get("a").is_some());
+
+        let stats = cache.evict().unwrap();
+        assert_eq!(stats.evicted, 1);
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        drop(cache);
+
+        let reopened = PersistentCache::open(&dir, None, quiet());
+        assert!(reopened.get("b").is_none());
+        drop(reopened);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn prefix_caps_count_stored_and_batched_entries() {
+        let dir = temp_dir("prefix-caps");
+        fs::write(
+            dir.join(CACHE_POLICY_FILE),
+            r#"{"prefixLimits": {"feed:": 60}}"#,
+        )
+        .unwrap();
+        let cache = PersistentCache::open(&dir, None, quiet());
+        let none = HashMap::new();
+        // `{"data":"x"}` is 12 bytes.
+        cache.put("feed:a".into(), json!({"data": "x"})).unwrap();
+        assert!(cache.check_write("feed:b", 48, &none).is_ok());
+        let refusal = cache.check_write("feed:b", 49, &none).unwrap_err();
+        assert_eq!(refusal.code, crate::cache_limits::RefusalCode::PrefixFull);
+        // Replacing a key does not count its old value.
+        assert!(cache.check_write("feed:a", 60, &none).is_ok());
+        let batch = HashMap::from([("feed:c".to_string(), 20)]);
+        assert!(cache.check_write("feed:b", 30, &batch).is_err());
+        cache.flush().unwrap();
+        drop(cache);
+
+        let reopened = PersistentCache::open(&dir, None, quiet());
+        assert!(reopened.check_write("feed:b", 49, &none).is_err());
+        reopened.remove("feed:a").unwrap();
+        assert!(reopened.check_write("feed:b", 60, &none).is_ok());
+        drop(reopened);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn remove_prefix_matches_literally_and_refuses_empty() {
+        let cache = PersistentCache::in_memory();
+        cache.put("feed:a".into(), json!({"updatedAt": 5})).unwrap();
+        cache.put("feed:b".into(), json!({"updatedAt": 6})).unwrap();
+        cache.put("feed%x".into(), json!({"updatedAt": 7})).unwrap();
+        cache
+            .put("summary:brief".into(), json!({"updatedAt": 8}))
+            .unwrap();
+
+        assert!(cache.remove_prefix("").is_err());
+        assert_eq!(cache.remove_prefix("feed:").unwrap(), 2);
+        assert!(cache.get("feed:a").is_none());
+
+        let listed: Vec<_> = cache
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.key, e.updated_at))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![("feed%x".to_string(), 7), ("summary:brief".to_string(), 8)]
+        );
+        let stats = cache.stats().unwrap();
+        assert_eq!(stats.entry_count, 2);
+        assert_eq!(
+            stats.total_bytes,
+            2 * json!({"updatedAt": 8}).to_string().len() as u64
+        );
+    }
+
+    #[test]
+    fn batch_writes_and_reads_round_trip() {
+        let dir = temp_dir("batch");
+        {
+            let cache = PersistentCache::open(&dir, None, quiet());
+            cache
+                .put_many(vec![
+                    ("panel:a".into(), json!({"updatedAt": 1})),
+                    ("panel:b".into(), json!({"updatedAt": 2})),
+                ])
+                .unwrap();
+        }
+        let cache = PersistentCache::open(&dir, None, quiet());
+        let found = cache.get_many(&["panel:a".into(), "panel:missing".into(), "panel:b".into()]);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found.get("panel:b"), Some(&json!({"updatedAt": 2})));
+        assert!(!found.contains_key("panel:missing"));
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn writes_are_deferred_until_flush_and_coalesced() {
+        let dir = temp_dir("deferred");
+        let db_path = dir.join(CACHE_DB_FILE);
+        let cache = PersistentCache::open(&dir, None, quiet());
+        cache
+            .put("panel:a".into(), json!({"updatedAt": 1}))
+            .unwrap();
+        cache
+            .put("panel:a".into(), json!({"updatedAt": 2}))
+            .unwrap();
+        cache
+            .put("panel:b".into(), json!({"updatedAt": 3}))
+            .unwrap();
+        cache.remove("panel:b").unwrap();
+
+        let rows = |conn: &Connection| -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM cache_entries", [], |row| row.get(0))
+                .unwrap()
+        };
+        let observer = Connection::open(&db_path).unwrap();
+        assert_eq!(rows(&observer), 0);
+
+        cache.wait_for_dirty();
+        let report = cache.flush_pending().unwrap();
+        assert_eq!(report.entries, 2);
+        assert_eq!(rows(&observer), 1);
+        let stored: String = observer
+            .query_row(
+                "SELECT value FROM cache_entries WHERE key = 'panel:a'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored, json!({"updatedAt": 2}).to_string());
+        assert_eq!(cache.flush_pending().unwrap().entries, 0);
+        drop(observer);
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn raw_value(db_path: &Path, key: &str) -> SqlValue {
+        let conn = Connection::open(db_path).unwrap();
+        conn.query_row(
+            "SELECT value FROM cache_entries WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn encrypts_plaintext_rows_on_open_and_reads_them_back() {
+        let dir = temp_dir("encrypt");
+        let db_path = dir.join(CACHE_DB_FILE);
+        let key = CacheCipher::generate_key();
+        {
+            let cache = PersistentCache::open(&dir, None, quiet());
+            cache
+                .put("panel:a".into(), json!({"updatedAt": 1, "data": "secret"}))
+                .unwrap();
+            cache.flush().unwrap();
+        }
+        assert!(matches!(raw_value(&db_path, "panel:a"), SqlValue::Text(_)));
+
+        {
+            let cache = PersistentCache::open(&dir, Some(CacheCipher::new(&key)), quiet());
+            assert_eq!(
+                cache.get("panel:a"),
+                Some(json!({"updatedAt": 1, "data": "secret"}))
+            );
+            cache
+                .put("panel:b".into(), json!({"updatedAt": 2}))
+                .unwrap();
+        }
+        for name in ["panel:a", "panel:b"] {
+            match raw_value(&db_path, name) {
+                SqlValue::Blob(bytes) => assert!(cache_crypto::is_sealed(&bytes)),
+                other => panic!("{name} stored as {other:?}"),
+            }
+        }
+        assert!(
+            !backup_path(&db_path, 1).exists(),
+            "plaintext backup generation kept"
+        );
+
+        let cache = PersistentCache::open(&dir, Some(CacheCipher::new(&key)), quiet());
+        assert_eq!(cache.get("panel:b"), Some(json!({"updatedAt": 2})));
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn discards_entries_sealed_with_a_different_key() {
+        let dir = temp_dir("wrong-key");
+        {
+            let cache = PersistentCache::open(
+                &dir,
+                Some(CacheCipher::new(&CacheCipher::generate_key())),
+                quiet(),
+            );
+            cache
+                .put("panel:a".into(), json!({"updatedAt": 1}))
+                .unwrap();
+        }
+        let (log, lines) = capture();
+        let cache = PersistentCache::open(
+            &dir,
+            Some(CacheCipher::new(&CacheCipher::generate_key())),
+            log,
+        );
+        assert!(cache.get("panel:a").is_none());
+        assert_eq!(cache.stats().unwrap().entry_count, 0);
+        assert!(lines
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|l| l.starts_with("ERROR") && l.contains("discarding 1 entries")));
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn change_listener_sees_writes_deletes_and_prefix_clears() {
+        let cache = PersistentCache::in_memory();
+        let seen = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        cache.set_change_listener(Box::new(move |change| {
+            sink.lock().unwrap().push(change.clone())
+        }));
+
+        cache
+            .put("feed:a".into(), json!({"updatedAt": 10}))
+            .unwrap();
+        cache.remove("feed:a").unwrap();
+        cache.remove("feed:missing").unwrap();
+        cache.put("feed:b".into(), json!({"data": 1})).unwrap();
+        cache.remove_prefix("feed:").unwrap();
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(
+            *seen,
+            vec![
+                CacheChange {
+                    key: "feed:a".into(),
+                    updated_at: Some(10),
+                    deleted: false
+                },
+                CacheChange::deleted("feed:a".into()),
+                CacheChange {
+                    key: "feed:b".into(),
+                    updated_at: None,
+                    deleted: false
+                },
+                CacheChange::deleted("feed:b".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn import_merge_keeps_newer_local_entries_and_replace_drops_the_rest() {
+        let cache = PersistentCache::in_memory();
+        cache
+            .put("feed:a".into(), json!({"updatedAt": 50}))
+            .unwrap();
+        cache.put("feed:b".into(), json!({"updatedAt": 5})).unwrap();
+        cache
+            .put("feed:local".into(), json!({"updatedAt": 1}))
+            .unwrap();
+
+        let mut incoming = Map::new();
+        incoming.insert("feed:a".into(), json!({"updatedAt": 10}));
+        incoming.insert("feed:b".into(), json!({"updatedAt": 20}));
+        incoming.insert("feed:new".into(), json!({"updatedAt": 30}));
+
+        let merged = cache.import(incoming.clone(), ImportMode::Merge);
+        assert_eq!(
+            merged,
+            ImportReport {
+                imported: 2,
+                kept_newer: 1,
+                removed: 0,
+                refused: Vec::new(),
+            }
+        );
+        assert_eq!(cache.get("feed:a"), Some(json!({"updatedAt": 50})));
+        assert_eq!(cache.get("feed:b"), Some(json!({"updatedAt": 20})));
+        assert!(cache.get("feed:local").is_some());
+
+        let replaced = cache.import(incoming.clone(), ImportMode::Replace);
+        assert_eq!(
+            replaced,
+            ImportReport {
+                imported: 3,
+                kept_newer: 0,
+                removed: 1,
+                refused: Vec::new(),
+            }
+        );
+        assert_eq!(cache.snapshot(), incoming);
+    }
+
+    #[test]
+    fn import_runs_entries_through_the_write_limits() {
+        let dir = temp_dir("import-limits");
+        fs::write(
+            dir.join(CACHE_POLICY_FILE),
+            r#"{"prefixLimits": {"feed:": 30}, "entryLimits": {"summary:": 20}}"#,
+        )
+        .unwrap();
+        let cache = PersistentCache::open(&dir, None, quiet());
+        let mut incoming = Map::new();
+        // `{"data":"x"}` is 12 bytes, so the third feed entry overflows the cap.
+        incoming.insert("feed:a".into(), json!({"data": "x"}));
+        incoming.insert("feed:b".into(), json!({"data": "y"}));
+        incoming.insert("feed:c".into(), json!({"data": "z"}));
+        incoming.insert("summary:big".into(), json!({"data": "0123456789abcdef"}));
+
+        let report = cache.import(incoming, ImportMode::Merge);
+        assert_eq!(report.imported, 2);
+        let refused: Vec<&str> = report.refused.iter().map(|r| r.key.as_str()).collect();
+        assert_eq!(refused, ["feed:c", "summary:big"]);
+        assert!(cache.get("feed:c").is_none());
+        assert!(cache.get("summary:big").is_none());
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn large_values_are_compressed_and_legacy_text_rows_still_load() {
+        let dir = temp_dir("compress");
+        let db_path = dir.join(CACHE_DB_FILE);
+        let items: Vec<Value> = (0..200)
+            .map(|i| json!({"title": format!("headline {i}"), "source": "wire"}))
+            .collect();
+        let big = json!({"updatedAt": 1, "data": items});
+        {
+            let cache = PersistentCache::open(&dir, None, quiet());
+            cache.put("panel:big".into(), big.clone()).unwrap();
+            cache
+                .put("panel:small".into(), json!({"updatedAt": 2}))
+                .unwrap();
+        }
+        match raw_value(&db_path, "panel:big") {
+            SqlValue::Blob(bytes) => {
+                assert!(bytes.starts_with(COMPRESSED_MAGIC));
+                assert!(bytes.len() < big.to_string().len() / 4);
+            }
+            other => panic!("big value stored as {other:?}"),
+        }
+        assert!(matches!(
+            raw_value(&db_path, "panel:small"),
+            SqlValue::Text(_)
+        ));
+
+        let cache = PersistentCache::open(&dir, None, quiet());
+        assert_eq!(cache.get("panel:big"), Some(big));
+        assert_eq!(cache.get("panel:small"), Some(json!({"updatedAt": 2})));
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn history_keeps_recent_versions_per_rule() {
+        let dir = temp_dir("history");
+        fs::write(
+            dir.join(CACHE_POLICY_FILE),
+            r#"{"history": {"panel:": {"maxVersions": 3}, "recent:": {"maxAgeSeconds": 60}, "risk-scores:": {}}}"#,
+        )
+        .unwrap();
+        let cipher = CacheCipher::new(&CacheCipher::generate_key());
+        let cache = PersistentCache::open(&dir, Some(cipher), quiet());
+        assert!(cache.policy.history_for("risk-scores:latest").is_none());
+
+        for version in 1..=5 {
+            cache
+                .put(
+                    "panel:a".into(),
+                    json!({"updatedAt": version, "data": version}),
+                )
+                .unwrap();
+            cache.flush_pending().unwrap();
+        }
+        // Coalesced into the previous flush window: only the last one counts.
+        cache
+            .put("panel:a".into(), json!({"updatedAt": 6, "data": 6}))
+            .unwrap();
+        cache
+            .put("panel:a".into(), json!({"updatedAt": 7, "data": 7}))
+            .unwrap();
+        let versions: Vec<i64> = cache
+            .history("panel:a", None)
+            .unwrap()
+            .iter()
+            .map(|entry| entry.updated_at)
+            .collect();
+        assert_eq!(versions, vec![4, 5, 7]);
+        let since = cache.history("panel:a", Some(5)).unwrap();
+        assert_eq!(since.len(), 2);
+        assert_eq!(since[0].value, json!({"updatedAt": 5, "data": 5}));
+
+        let now = now_ms();
+        cache
+            .put("recent:b".into(), json!({"updatedAt": now - 120_000}))
+            .unwrap();
+        cache.flush_pending().unwrap();
+        assert!(cache.history("recent:b", None).unwrap().is_empty());
+        cache
+            .put("recent:b".into(), json!({"updatedAt": now}))
+            .unwrap();
+        assert_eq!(cache.history("recent:b", None).unwrap().len(), 1);
+        cache.evict_at(now + 120_000).unwrap();
+        assert!(cache.history("recent:b", None).unwrap().is_empty());
+
+        cache.put("other".into(), json!({"updatedAt": 1})).unwrap();
+        assert!(cache.history("other", None).unwrap().is_empty());
+        cache.remove("panel:a").unwrap();
+        assert!(cache.history("panel:a", None).unwrap().is_empty());
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn history_is_trimmed_oldest_first_to_its_byte_quota() {
+        let dir = temp_dir("history-quota");
+        // Each version below is 29 bytes of JSON, so 80 bytes holds two.
+        fs::write(
+            dir.join(CACHE_POLICY_FILE),
+            r#"{"history": {"panel:": {"maxVersions": 10}}, "historyMaxBytes": 80}"#,
+        )
+        .unwrap();
+        let cache = PersistentCache::open(&dir, None, quiet());
+        for (key, version) in [
+            ("panel:a", 1),
+            ("panel:a", 2),
+            ("panel:b", 3),
+            ("panel:a", 4),
+        ] {
+            cache
+                .put(key.into(), json!({"updatedAt": version, "data": "xxxx"}))
+                .unwrap();
+            cache.flush_pending().unwrap();
+        }
+        assert_eq!(cache.history("panel:a", None).unwrap().len(), 3);
+
+        cache.evict().unwrap();
+        let versions = |key: &str| -> Vec<i64> {
+            cache
+                .history(key, None)
+                .unwrap()
+                .iter()
+                .map(|entry| entry.updated_at)
+                .collect()
+        };
+        assert_eq!(versions("panel:a"), vec![4]);
+        assert_eq!(versions("panel:b"), vec![3]);
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn imports_legacy_json_once() {
+        let dir = temp_dir("migrate");
+        let legacy = dir.join(LEGACY_CACHE_FILE);
+        fs::write(
+            &legacy,
+            r#"{"feed":{"key":"feed","updatedAt":5,"data":"x"}}"#,
+        )
+        .unwrap();
+
+        let cache = PersistentCache::open(&dir, None, quiet());
+        assert_eq!(
+            cache.get("feed"),
+            Some(json!({"key": "feed", "updatedAt": 5, "data": "x"}))
+        );
+        assert!(!legacy.exists());
+        assert!(migrated_path(&legacy).exists());
+
+        cache.remove("feed").unwrap();
+        drop(cache);
+        let cache = PersistentCache::open(&dir, None, quiet());
+        assert_eq!(cache.get("feed"), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn detects_sealed_entries_without_the_key() {
+        let dir = temp_dir("sealed");
+        let db_path = dir.join(CACHE_DB_FILE);
+        assert!(!holds_sealed_entries(&db_path));
+        let cache = PersistentCache::open(&dir, None, quiet());
+        cache.put("feed".into(), json!({"updatedAt": 1})).unwrap();
+        cache.flush().unwrap();
+        drop(cache);
+        assert!(!holds_sealed_entries(&db_path));
+
+        let cipher = CacheCipher::new(&CacheCipher::generate_key());
+        let cache = PersistentCache::open(&dir, Some(cipher), quiet());
+        cache.flush().unwrap();
+        drop(cache);
+        assert!(holds_sealed_entries(&db_path));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keeps_legacy_json_when_database_is_unavailable() {
+        let dir = temp_dir("migrate-memory");
+        let legacy = dir.join(LEGACY_CACHE_FILE);
+        fs::write(&legacy, r#"{"feed":{"key":"feed","updatedAt":5}}"#).unwrap();
+        // A directory where the database should be, which recovery cannot
+        // move aside either.
+        let db_path = dir.join(CACHE_DB_FILE);
+        fs::create_dir_all(&db_path).unwrap();
+        fs::create_dir_all(sibling_path(&db_path, ".corrupt").join("x")).unwrap();
+
+        let cache = PersistentCache::open(&dir, None, quiet());
+        assert!(cache.path.is_none());
+        assert!(legacy.exists());
+        assert!(!migrated_path(&legacy).exists());
+        drop(cache);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,171 @@
+//! Append-only record of changes to stored secrets, kept in the logs dir as
+//! one JSON object per line. Entries name the key or profile, never a value.
+//! Refused attempts are recorded as well as completed ones.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+pub(crate) const AUDIT_LOG_FILE: &str = "secret-audit.log";
+/// Most entries `read_entries` returns when the caller sets no limit.
+pub(crate) const DEFAULT_READ_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum AuditOperation {
+    Set,
+    Delete,
+    Import,
+    SetExpiry,
+    Reveal,
+    /// Unlocking (or creating) the file vault.
+    Unlock,
+    CreateProfile,
+    /// Replaces every key of the active profile.
+    SwitchProfile,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum AuditResult {
+    Ok,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AuditEntry {
+    /// Unix ms.
+    pub timestamp: i64,
+    /// `None` for vault and profile operations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    pub operation: AuditOperation,
+    /// Label of the webview that asked for the change.
+    pub window: String,
+    pub result: AuditResult,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    pub(crate) fn new<T>(
+        timestamp: i64,
+        operation: AuditOperation,
+        window: &str,
+        outcome: &Result<T, String>,
+    ) -> Self {
+        AuditEntry {
+            timestamp,
+            key: None,
+            profile: None,
+            operation,
+            window: window.to_string(),
+            result: if outcome.is_ok() {
+                AuditResult::Ok
+            } else {
+                AuditResult::Failed
+            },
+            error: outcome.as_ref().err().cloned(),
+        }
+    }
+
+    pub(crate) fn with_key(mut self, key: &str) -> Self {
+        self.key = Some(key.to_string());
+        self
+    }
+
+    pub(crate) fn with_profile(mut self, profile: &str) -> Self {
+        self.profile = Some(profile.to_string());
+        self
+    }
+}
+
+pub(crate) fn append_entry(path: &Path, entry: &AuditEntry) -> Result<(), String> {
+    let line =
+        serde_json::to_string(entry).map_err(|e| format!("Failed to encode audit entry: {e}"))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    writeln!(file, "{line}").map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// The last `limit` entries, newest first. A missing log reads as empty;
+/// lines that do not parse are skipped.
+pub(crate) fn read_entries(path: &Path, limit: usize) -> Result<Vec<AuditEntry>, String> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {e}", path.display())),
+    };
+    Ok(contents
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn entries_append_and_read_back_newest_first() {
+        let dir = temp_dir("audit");
+        let path = dir.join(AUDIT_LOG_FILE);
+        assert!(read_entries(&path, 10).unwrap().is_empty());
+
+        let saved: Result<(), String> = Ok(());
+        append_entry(
+            &path,
+            &AuditEntry::new(1, AuditOperation::Set, "settings", &saved).with_key("GROQ_API_KEY"),
+        )
+        .unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"not json\n")
+            .unwrap();
+        let failed: Result<(), String> = Err("Failed to save secrets".to_string());
+        append_entry(
+            &path,
+            &AuditEntry::new(2, AuditOperation::Delete, "main", &failed).with_key("GROQ_API_KEY"),
+        )
+        .unwrap();
+        append_entry(
+            &path,
+            &AuditEntry::new(3, AuditOperation::SwitchProfile, "settings", &saved)
+                .with_profile("work"),
+        )
+        .unwrap();
+
+        let entries = read_entries(&path, 10).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].operation, AuditOperation::SwitchProfile);
+        assert_eq!(entries[0].profile.as_deref(), Some("work"));
+        assert_eq!(entries[0].key, None);
+        let json = serde_json::to_value(&entries[0]).unwrap();
+        assert_eq!(json["operation"], "switchProfile");
+        assert!(json.get("key").is_none());
+
+        let entries = &entries[1..];
+        assert_eq!(entries[0].operation, AuditOperation::Delete);
+        assert_eq!(entries[0].result, AuditResult::Failed);
+        assert_eq!(entries[0].error.as_deref(), Some("Failed to save secrets"));
+        assert_eq!(entries[1].window, "settings");
+        assert_eq!(entries[1].error, None);
+        assert_eq!(entries[1].key.as_deref(), Some("GROQ_API_KEY"));
+        assert_eq!(read_entries(&path, 1).unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+//! Per-key bookkeeping kept next to each secret in the vault: when it was
+//! set, when it last passed or failed a live check, and when the user
+//! expects it to expire. None of it is sensitive, so it can be shown to any
+//! trusted window.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::secret_registry::SecretKind;
+use crate::secret_validation::ValidationStatus;
+
+/// Keys expiring within this many milliseconds are flagged for renewal.
+pub(crate) const EXPIRY_REMINDER_WINDOW_MS: i64 = 14 * 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SecretMeta {
+    /// Unix ms; `None` for keys saved before metadata was tracked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub set_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_validated_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_result: Option<ValidationStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+}
+
+impl SecretMeta {
+    pub(crate) fn set_now(now: i64) -> Self {
+        SecretMeta {
+            set_at: Some(now),
+            ..SecretMeta::default()
+        }
+    }
+}
+
+/// Returned by `get_secret_status`; never includes the value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SecretStatus {
+    pub key: String,
+    pub present: bool,
+    #[serde(flatten)]
+    pub meta: SecretMeta,
+    pub expired: bool,
+    /// Not expired yet, but within [`EXPIRY_REMINDER_WINDOW_MS`].
+    pub expires_soon: bool,
+}
+
+impl SecretStatus {
+    pub(crate) fn new(key: &str, meta: Option<&SecretMeta>, now: i64) -> Self {
+        let meta = meta.cloned();
+        let expires_at = meta.as_ref().and_then(|m| m.expires_at);
+        let expired = expires_at.is_some_and(|at| at <= now);
+        SecretStatus {
+            key: key.to_string(),
+            present: meta.is_some(),
+            expired,
+            expires_soon: !expired
+                && expires_at.is_some_and(|at| at - now <= EXPIRY_REMINDER_WINDOW_MS),
+            meta: meta.unwrap_or_default(),
+        }
+    }
+}
+
+pub(crate) fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Credentials shorter than this show no characters in the masked view.
+const MASK_MIN_LEN: usize = 12;
+const MASK_VISIBLE_CHARS: usize = 4;
+
+/// What the webview sees of a stored key in place of its value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MaskedSecret {
+    #[serde(flatten)]
+    pub status: SecretStatus,
+    /// Last characters of a credential long enough to spare them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_four: Option<String>,
+    /// Full value of settings that are not credentials (URLs, model names).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Whether a value is stored and matches the key's registry pattern.
+    pub valid: bool,
+}
+
+impl MaskedSecret {
+    pub(crate) fn new(
+        status: SecretStatus,
+        kind: SecretKind,
+        value: Option<&str>,
+        valid: bool,
+    ) -> Self {
+        let (last_four, value) = match (kind, value) {
+            (SecretKind::Secret, Some(value)) => {
+                let chars: Vec<char> = value.chars().collect();
+                let tail = (chars.len() >= MASK_MIN_LEN)
+                    .then(|| chars[chars.len() - MASK_VISIBLE_CHARS..].iter().collect());
+                (tail, None)
+            }
+            (_, value) => (None, value.map(str::to_string)),
+        };
+        MaskedSecret {
+            status,
+            last_four,
+            value,
+            valid,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masked_view_hides_credentials_but_not_settings() {
+        let status = |key: &str| SecretStatus::new(key, Some(&SecretMeta::default()), 0);
+        let long = MaskedSecret::new(
+            status("GROQ_API_KEY"),
+            SecretKind::Secret,
+            Some("gsk_abcdefgh1234"),
+            true,
+        );
+        assert_eq!(long.last_four.as_deref(), Some("1234"));
+        assert_eq!(long.value, None);
+        let short = MaskedSecret::new(
+            status("FRED_API_KEY"),
+            SecretKind::Secret,
+            Some("abcd1234"),
+            true,
+        );
+        assert_eq!((short.last_four, short.value), (None, None));
+        let url = MaskedSecret::new(
+            status("OLLAMA_API_URL"),
+            SecretKind::Url,
+            Some("http://127.0.0.1:11434"),
+            true,
+        );
+        assert_eq!(url.value.as_deref(), Some("http://127.0.0.1:11434"));
+
+        let json = serde_json::to_value(&long).unwrap();
+        assert_eq!(json["key"], "GROQ_API_KEY");
+        assert_eq!(json["lastFour"], "1234");
+        assert!(!json.to_string().contains("gsk_"));
+    }
+}
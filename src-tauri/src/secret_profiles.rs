@@ -0,0 +1,440 @@
+//! Named credential profiles inside the secrets vault.
+//!
+//! The vault holds one secret map per profile plus the name of the active
+//! one; only the active profile is exposed to the webview and the sidecar.
+//! Vaults written before profiles existed are a flat `{key: value}` map and
+//! load as a single `default` profile. Each stored key also has a
+//! [`SecretMeta`] entry; vaults written before metadata was tracked get
+//! empty entries on load.
+//!
+//! In the OS keyring the vault is one entry while it fits, and otherwise is
+//! split across numbered parts that the main entry points to. Each save
+//! writes its parts under a new generation, so the main entry only ever
+//! points at a complete set.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::secret_metadata::{SecretMeta, SecretStatus};
+use crate::secret_validation::ValidationStatus;
+
+pub(crate) const DEFAULT_PROFILE: &str = "default";
+const MAX_PROFILE_NAME_LEN: usize = 32;
+/// Windows Credential Manager stores a password as UTF-16 and caps it at
+/// 2560 bytes, which one full profile with its metadata already exceeds.
+const KEYRING_ENTRY_MAX_CHARS: usize = 1280;
+/// Past this the vault holds far more than the registry's keys.
+const MAX_KEYRING_PARTS: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProfileVault {
+    pub active_profile: String,
+    pub profiles: BTreeMap<String, HashMap<String, String>>,
+    /// Profile name -> key -> metadata, covering exactly the stored keys.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, BTreeMap<String, SecretMeta>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredVault {
+    Profiles(ProfileVault),
+    Flat(HashMap<String, String>),
+}
+
+/// Held by the main keyring entry in place of a vault too large for it:
+/// the vault is in parts `1..=parts` of `generation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct KeyringManifest {
+    pub generation: u64,
+    pub parts: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProfileSummary {
+    pub name: String,
+    pub key_count: usize,
+}
+
+/// Returned by the profile commands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProfileList {
+    pub active: String,
+    pub profiles: Vec<ProfileSummary>,
+}
+
+pub(crate) fn validate_profile_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_PROFILE_NAME_LEN
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid profile name '{name}': use 1-{MAX_PROFILE_NAME_LEN} letters, digits, '-' or '_'"
+        ))
+    }
+}
+
+fn sanitize(secrets: HashMap<String, String>, supported: &[&str]) -> HashMap<String, String> {
+    secrets
+        .into_iter()
+        .filter(|(k, v)| supported.contains(&k.as_str()) && !v.trim().is_empty())
+        .map(|(k, v)| (k, v.trim().to_string()))
+        .collect()
+}
+
+/// Split `s` into pieces of at most `max` UTF-16 units, on char boundaries.
+fn split_utf16(s: &str, max: usize) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let mut units = 0;
+    for c in s.chars() {
+        if units + c.len_utf16() > max {
+            parts.push(std::mem::take(&mut part));
+            units = 0;
+        }
+        part.push(c);
+        units += c.len_utf16();
+    }
+    if !part.is_empty() {
+        parts.push(part);
+    }
+    parts
+}
+
+/// The parts the main keyring entry points to, or `None` when it holds the
+/// vault itself.
+pub(crate) fn keyring_manifest(main_entry: &str) -> Option<KeyringManifest> {
+    serde_json::from_str(main_entry).ok()
+}
+
+impl ProfileVault {
+    /// A vault with `secrets` as its only, active profile.
+    pub(crate) fn single(secrets: HashMap<String, String>) -> Self {
+        let mut vault = ProfileVault {
+            active_profile: DEFAULT_PROFILE.to_string(),
+            profiles: BTreeMap::from([(DEFAULT_PROFILE.to_string(), secrets)]),
+            metadata: BTreeMap::new(),
+        };
+        vault.normalize_metadata();
+        vault
+    }
+
+    /// Drop metadata of keys no longer stored and add empty entries for
+    /// keys that have none.
+    fn normalize_metadata(&mut self) {
+        let mut metadata = std::mem::take(&mut self.metadata);
+        self.metadata = self
+            .profiles
+            .iter()
+            .map(|(name, secrets)| {
+                let mut previous = metadata.remove(name).unwrap_or_default();
+                let meta = secrets
+                    .keys()
+                    .map(|key| (key.clone(), previous.remove(key).unwrap_or_default()))
+                    .collect();
+                (name.clone(), meta)
+            })
+            .collect();
+    }
+
+    /// Parse a stored vault in either format, dropping unsupported and
+    /// blank keys from every profile.
+    pub(crate) fn parse(json: &[u8], supported: &[&str]) -> Result<Self, String> {
+        let stored: StoredVault =
+            serde_json::from_slice(json).map_err(|e| format!("Secrets vault is malformed: {e}"))?;
+        let mut vault = match stored {
+            StoredVault::Flat(secrets) => {
+                return Ok(ProfileVault::single(sanitize(secrets, supported)))
+            }
+            StoredVault::Profiles(vault) => vault,
+        };
+        vault.profiles = std::mem::take(&mut vault.profiles)
+            .into_iter()
+            .filter(|(name, _)| validate_profile_name(name).is_ok())
+            .map(|(name, secrets)| (name, sanitize(secrets, supported)))
+            .collect();
+        if validate_profile_name(&vault.active_profile).is_err() {
+            vault.active_profile = DEFAULT_PROFILE.to_string();
+        }
+        vault
+            .profiles
+            .entry(vault.active_profile.clone())
+            .or_default();
+        vault.normalize_metadata();
+        Ok(vault)
+    }
+
+    pub(crate) fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| format!("Failed to serialize vault: {e}"))
+    }
+
+    /// The main keyring entry and the parts of `generation` to store before
+    /// it; no parts when the vault fits in the main entry.
+    pub(crate) fn to_keyring_entries(
+        &self,
+        generation: u64,
+    ) -> Result<(String, Vec<String>), String> {
+        let json = self.to_json()?;
+        if json.encode_utf16().count() <= KEYRING_ENTRY_MAX_CHARS {
+            return Ok((json, Vec::new()));
+        }
+        let parts = split_utf16(&json, KEYRING_ENTRY_MAX_CHARS);
+        if parts.len() > MAX_KEYRING_PARTS {
+            return Err(format!(
+                "Secrets vault is too large for the OS keyring ({} bytes); remove unused keys",
+                json.len()
+            ));
+        }
+        let manifest = serde_json::to_string(&KeyringManifest {
+            generation,
+            parts: parts.len(),
+        })
+        .map_err(|e| format!("Failed to serialize vault: {e}"))?;
+        Ok((manifest, parts))
+    }
+
+    pub(crate) fn active(&self) -> &HashMap<String, String> {
+        &self.profiles[&self.active_profile]
+    }
+
+    /// Replace the active profile's secrets. Keys whose value changed start
+    /// over with `set_at = now` and no validation or expiry.
+    pub(crate) fn set_active_secrets(&mut self, secrets: HashMap<String, String>, now: i64) {
+        let previous = self
+            .profiles
+            .insert(self.active_profile.clone(), secrets)
+            .unwrap_or_default();
+        let meta = self
+            .metadata
+            .entry(self.active_profile.clone())
+            .or_default();
+        for (key, value) in &self.profiles[&self.active_profile] {
+            if previous.get(key) != Some(value) {
+                meta.insert(key.clone(), SecretMeta::set_now(now));
+            }
+        }
+        self.normalize_metadata();
+    }
+
+    /// Record a live check of `value`, if it is still the active value of
+    /// `key`. Returns whether anything was recorded.
+    pub(crate) fn record_validation(
+        &mut self,
+        key: &str,
+        value: &str,
+        result: ValidationStatus,
+        now: i64,
+    ) -> bool {
+        if self.active().get(key).map(String::as_str) != Some(value) {
+            return false;
+        }
+        let meta = self
+            .metadata
+            .entry(self.active_profile.clone())
+            .or_default()
+            .entry(key.to_string())
+            .or_default();
+        meta.last_validated_at = Some(now);
+        meta.last_result = Some(result);
+        true
+    }
+
+    /// Set or clear when the active value of `key` is expected to expire.
+    pub(crate) fn set_expiry(&mut self, key: &str, expires_at: Option<i64>) -> Result<(), String> {
+        if !self.active().contains_key(key) {
+            return Err(format!("{key} is not set"));
+        }
+        self.metadata
+            .entry(self.active_profile.clone())
+            .or_default()
+            .entry(key.to_string())
+            .or_default()
+            .expires_at = expires_at;
+        Ok(())
+    }
+
+    /// Status of each of `keys` in the active profile.
+    pub(crate) fn status(&self, keys: &[&str], now: i64) -> Vec<SecretStatus> {
+        let meta = self.metadata.get(&self.active_profile);
+        keys.iter()
+            .map(|key| SecretStatus::new(key, meta.and_then(|m| m.get(*key)), now))
+            .collect()
+    }
+
+    /// Add an empty profile, or a copy of the active one. Does not switch.
+    pub(crate) fn create(&mut self, name: &str, copy_active: bool) -> Result<(), String> {
+        validate_profile_name(name)?;
+        if self.profiles.contains_key(name) {
+            return Err(format!("Profile '{name}' already exists"));
+        }
+        let (secrets, meta) = if copy_active {
+            let meta = self.metadata.get(&self.active_profile).cloned();
+            (self.active().clone(), meta.unwrap_or_default())
+        } else {
+            (HashMap::new(), BTreeMap::new())
+        };
+        self.profiles.insert(name.to_string(), secrets);
+        self.metadata.insert(name.to_string(), meta);
+        Ok(())
+    }
+
+    pub(crate) fn switch(&mut self, name: &str) -> Result<(), String> {
+        if !self.profiles.contains_key(name) {
+            return Err(format!("Unknown profile '{name}'"));
+        }
+        self.active_profile = name.to_string();
+        Ok(())
+    }
+
+    pub(crate) fn list(&self) -> ProfileList {
+        ProfileList {
+            active: self.active_profile.clone(),
+            profiles: self
+                .profiles
+                .iter()
+                .map(|(name, secrets)| ProfileSummary {
+                    name: name.clone(),
+                    key_count: secrets.len(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUPPORTED: &[&str] = &["OPENROUTER_API_KEY", "ACLED_ACCESS_TOKEN"];
+
+    #[test]
+    fn legacy_flat_vault_becomes_default_profile_and_profiles_switch() {
+        let mut vault = ProfileVault::parse(
+            br#"{"OPENROUTER_API_KEY":" sk-personal ","ROGUE":"x","ACLED_ACCESS_TOKEN":""}"#,
+            SUPPORTED,
+        )
+        .unwrap();
+        assert_eq!(vault.active_profile, DEFAULT_PROFILE);
+        assert_eq!(
+            vault.active(),
+            &HashMap::from([("OPENROUTER_API_KEY".to_string(), "sk-personal".to_string())])
+        );
+
+        vault.create("org", false).unwrap();
+        assert!(vault.create("org", false).is_err());
+        assert!(vault.create("bad name", false).is_err());
+        assert!(vault.switch("missing").is_err());
+        vault.switch("org").unwrap();
+        assert!(vault.active().is_empty());
+        vault.set_active_secrets(
+            HashMap::from([("OPENROUTER_API_KEY".to_string(), "sk-org".to_string())]),
+            1_000,
+        );
+
+        let reparsed = ProfileVault::parse(vault.to_json().unwrap().as_bytes(), SUPPORTED).unwrap();
+        assert_eq!(reparsed, vault);
+        assert_eq!(
+            reparsed.list().profiles,
+            vec![
+                ProfileSummary {
+                    name: "default".into(),
+                    key_count: 1
+                },
+                ProfileSummary {
+                    name: "org".into(),
+                    key_count: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn metadata_is_migrated_and_tracks_value_changes() {
+        let mut vault = ProfileVault::parse(
+            br#"{"activeProfile":"default","profiles":{"default":{"OPENROUTER_API_KEY":"sk-1"}}}"#,
+            SUPPORTED,
+        )
+        .unwrap();
+        let status = vault.status(SUPPORTED, 0);
+        assert!(status[0].present && status[0].meta == SecretMeta::default());
+        assert!(!status[1].present);
+
+        assert!(vault.record_validation("OPENROUTER_API_KEY", "sk-1", ValidationStatus::Ok, 5));
+        assert!(!vault.record_validation("OPENROUTER_API_KEY", "sk-2", ValidationStatus::Ok, 5));
+        vault.set_expiry("OPENROUTER_API_KEY", Some(100)).unwrap();
+        assert!(vault.set_expiry("ACLED_ACCESS_TOKEN", Some(100)).is_err());
+        let status = &vault.status(SUPPORTED, 90)[0];
+        assert_eq!(status.meta.last_validated_at, Some(5));
+        assert_eq!(status.meta.last_result, Some(ValidationStatus::Ok));
+        assert!(status.expires_soon && !status.expired);
+        assert!(vault.status(SUPPORTED, 100)[0].expired);
+
+        // Re-saving an unchanged value keeps its metadata; a new value resets it.
+        let mut secrets = vault.active().clone();
+        vault.set_active_secrets(secrets.clone(), 50);
+        assert_eq!(vault.status(SUPPORTED, 0)[0].meta.expires_at, Some(100));
+        secrets.insert("OPENROUTER_API_KEY".into(), "sk-2".into());
+        secrets.insert("ACLED_ACCESS_TOKEN".into(), "tok".into());
+        vault.set_active_secrets(secrets, 60);
+        let status = vault.status(SUPPORTED, 0);
+        assert_eq!(status[0].meta, SecretMeta::set_now(60));
+        assert_eq!(status[1].meta, SecretMeta::set_now(60));
+
+        vault.create("copy", true).unwrap();
+        let reparsed = ProfileVault::parse(vault.to_json().unwrap().as_bytes(), SUPPORTED).unwrap();
+        assert_eq!(reparsed, vault);
+        assert_eq!(reparsed.metadata["copy"], reparsed.metadata["default"]);
+    }
+
+    #[test]
+    fn full_vault_is_split_into_keyring_sized_parts() {
+        let registry = crate::secret_registry::SecretRegistry::bundled();
+        let keys = registry.keys();
+        let secrets: HashMap<String, String> = keys
+            .iter()
+            .map(|key| {
+                (
+                    key.to_string(),
+                    format!("{}-{}", key.to_lowercase(), "x".repeat(64)),
+                )
+            })
+            .collect();
+        let mut vault = ProfileVault::single(HashMap::new());
+        vault.set_active_secrets(secrets.clone(), 1_760_000_000_000);
+        for key in &keys {
+            vault.record_validation(key, &secrets[*key], ValidationStatus::Ok, 1_760_000_000_000);
+            vault.set_expiry(key, Some(1_790_000_000_000)).unwrap();
+        }
+        vault.create("work", true).unwrap();
+
+        let (main, parts) = vault.to_keyring_entries(7).unwrap();
+        assert!(parts.len() > 1);
+        assert_eq!(
+            keyring_manifest(&main),
+            Some(KeyringManifest {
+                generation: 7,
+                parts: parts.len()
+            })
+        );
+        assert!(parts
+            .iter()
+            .all(|part| part.encode_utf16().count() <= KEYRING_ENTRY_MAX_CHARS));
+        let reparsed = ProfileVault::parse(parts.concat().as_bytes(), &keys).unwrap();
+        assert_eq!(reparsed, vault);
+
+        let small = ProfileVault::single(HashMap::from([("GROQ_API_KEY".into(), "gsk".into())]));
+        let (main, parts) = small.to_keyring_entries(8).unwrap();
+        assert!(parts.is_empty());
+        assert_eq!(keyring_manifest(&main), None);
+        assert_eq!(keyring_manifest(r#"{"generation":1,"parts":"1"}"#), None);
+    }
+}
@@ -0,0 +1,188 @@
+//! Registry of the secret keys the desktop app stores and hands to the
+//! sidecar.
+//!
+//! The list lives in `sidecar/secret-keys.json`, which the sidecar reads for
+//! its env update allowlist, so adding a data source is a one-file change.
+//! The same file is compiled in as a fallback for when the bundled resource
+//! cannot be read.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Path of the registry relative to the resource dir (and `src-tauri/`).
+pub(crate) const REGISTRY_FILE: &str = "sidecar/secret-keys.json";
+const BUNDLED_REGISTRY: &str = include_str!("../sidecar/secret-keys.json");
+const REGISTRY_VERSION: u32 = 1;
+
+static REGISTRY: OnceLock<SecretRegistry> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum SecretKind {
+    /// A credential; never shown in full once saved.
+    Secret,
+    /// An endpoint URL, shown in plain text.
+    Url,
+    /// Any other non-sensitive setting (model name, client id).
+    Text,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SecretKeyInfo {
+    pub key: String,
+    pub label: String,
+    pub provider: String,
+    pub kind: SecretKind,
+    /// Regex a value must match to be saved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub docs_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RegistryFile {
+    version: u32,
+    keys: Vec<SecretKeyInfo>,
+}
+
+#[derive(Debug)]
+pub(crate) struct SecretRegistry {
+    entries: Vec<SecretKeyInfo>,
+    /// Compiled `pattern`s, index-aligned with `entries`.
+    patterns: Vec<Option<Regex>>,
+}
+
+impl SecretRegistry {
+    pub(crate) fn parse(json: &str) -> Result<Self, String> {
+        let file: RegistryFile = serde_json::from_str(json)
+            .map_err(|e| format!("Secret key registry is malformed: {e}"))?;
+        if file.version != REGISTRY_VERSION {
+            return Err(format!(
+                "Unsupported secret key registry version {}",
+                file.version
+            ));
+        }
+        let mut seen = HashSet::new();
+        let mut patterns = Vec::with_capacity(file.keys.len());
+        for entry in &file.keys {
+            let valid_name = !entry.key.is_empty()
+                && entry
+                    .key
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+            if !valid_name {
+                return Err(format!(
+                    "Secret key registry has an invalid key name '{}'",
+                    entry.key
+                ));
+            }
+            if !seen.insert(entry.key.as_str()) {
+                return Err(format!("Secret key registry lists '{}' twice", entry.key));
+            }
+            let pattern = entry
+                .pattern
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| format!("Invalid pattern for {}: {e}", entry.key))?;
+            patterns.push(pattern);
+        }
+        Ok(SecretRegistry {
+            entries: file.keys,
+            patterns,
+        })
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        SecretRegistry::parse(&json)
+    }
+
+    pub(crate) fn bundled() -> Self {
+        SecretRegistry::parse(BUNDLED_REGISTRY).expect("bundled secret key registry is valid")
+    }
+
+    pub(crate) fn entries(&self) -> &[SecretKeyInfo] {
+        &self.entries
+    }
+
+    pub(crate) fn keys(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .map(|entry| entry.key.as_str())
+            .collect()
+    }
+
+    pub(crate) fn contains(&self, key: &str) -> bool {
+        self.info(key).is_some()
+    }
+
+    pub(crate) fn info(&self, key: &str) -> Option<&SecretKeyInfo> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+
+    /// `Err` with a message naming the key if `value` does not match its
+    /// pattern. Unknown keys are an error too.
+    pub(crate) fn check_value(&self, key: &str, value: &str) -> Result<(), String> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.key == key)
+            .ok_or_else(|| format!("Unsupported secret key: {key}"))?;
+        match &self.patterns[index] {
+            Some(pattern) if !pattern.is_match(value) => Err(format!(
+                "Value for {} does not look like a valid {}",
+                key, self.entries[index].label
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Install the registry loaded at startup. Later calls are ignored.
+pub(crate) fn init(registry: SecretRegistry) {
+    let _ = REGISTRY.set(registry);
+}
+
+/// The registry installed by [`init`], or the compiled-in copy.
+pub(crate) fn get() -> &'static SecretRegistry {
+    REGISTRY.get_or_init(SecretRegistry::bundled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_registry_loads_and_checks_values() {
+        let registry = SecretRegistry::bundled();
+        assert!(registry.contains("GROQ_API_KEY"));
+        assert!(registry.check_value("GROQ_API_KEY", "gsk_abc").is_ok());
+        assert!(registry.check_value("GROQ_API_KEY", "two words").is_err());
+        assert!(registry
+            .check_value("OLLAMA_API_URL", "http://127.0.0.1:11434")
+            .is_ok());
+        assert!(registry
+            .check_value("OLLAMA_API_URL", "ftp://host")
+            .is_err());
+        assert!(registry
+            .check_value("WORLDMONITOR_API_KEY", "short")
+            .is_err());
+        assert!(registry.check_value("ROGUE_KEY", "x").is_err());
+
+        let dup = r#"{"version":1,"keys":[
+            {"key":"A_KEY","label":"A","provider":"P","kind":"secret"},
+            {"key":"A_KEY","label":"A","provider":"P","kind":"secret"}]}"#;
+        assert!(SecretRegistry::parse(dup).unwrap_err().contains("twice"));
+        let bad_pattern = r#"{"version":1,"keys":[
+            {"key":"A_KEY","label":"A","provider":"P","kind":"url","pattern":"("}]}"#;
+        assert!(SecretRegistry::parse(bad_pattern).is_err());
+    }
+}
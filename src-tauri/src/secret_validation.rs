@@ -0,0 +1,586 @@
+//! Live checks of a secret against its provider, done from Rust so keys can
+//! be verified even when the sidecar fails to start.
+//!
+//! Each probe mirrors the sidecar's `validateSecretAgainstProvider` and hits
+//! the provider's cheapest authenticated endpoint. Keys without a live check
+//! are reported as `unchecked`.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use regex::Regex;
+use reqwest::{RequestBuilder, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(12);
+const OLLAMA_PROBE_TIMEOUT: Duration = Duration::from_secs(8);
+/// Some providers sit behind bot protection that rejects non-browser agents.
+const USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum ValidationStatus {
+    /// The provider accepted the key.
+    Ok,
+    /// The provider rejected the key, or the value is malformed.
+    Invalid,
+    /// The provider could not be reached or did not give a usable answer.
+    NetworkError,
+    /// The provider throttled the check; the key itself was not rejected.
+    RateLimited,
+    /// There is no live check for this key; it was stored as given.
+    Unchecked,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SecretValidation {
+    pub status: ValidationStatus,
+    pub message: String,
+}
+
+impl SecretValidation {
+    fn new(status: ValidationStatus, message: impl Into<String>) -> Self {
+        SecretValidation {
+            status,
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn invalid(message: impl Into<String>) -> Self {
+        SecretValidation::new(ValidationStatus::Invalid, message)
+    }
+
+    fn network_error(message: impl Into<String>) -> Self {
+        SecretValidation::new(ValidationStatus::NetworkError, message)
+    }
+}
+
+/// `Ok` carries the success message; anything else is an `Err` outcome.
+type Outcome = Result<String, SecretValidation>;
+
+struct ProbeResponse {
+    status: u16,
+    cf_ray: bool,
+    content_type: String,
+    body: String,
+}
+
+impl ProbeResponse {
+    fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap_or(Value::Null)
+    }
+
+    fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// A Cloudflare bot challenge page, which says nothing about the key.
+    fn is_cloudflare_challenge(&self) -> bool {
+        if self.status != 403 || !self.cf_ray {
+            return false;
+        }
+        let body = self.body.to_ascii_lowercase();
+        if !self.content_type.contains("text/html") && !body.contains("<html") {
+            return false;
+        }
+        [
+            "attention required",
+            "cf-browser-verification",
+            "__cf_chl",
+            "ray id",
+        ]
+        .iter()
+        .filter(|marker| body.contains(*marker))
+        .count()
+            >= 2
+    }
+
+    /// Intentionally broad; check for a Cloudflare challenge first.
+    fn is_auth_failure(&self) -> bool {
+        if self.status == 401 || self.status == 403 {
+            return true;
+        }
+        static PATTERN: OnceLock<Regex> = OnceLock::new();
+        PATTERN
+            .get_or_init(|| {
+                Regex::new(
+                    r"(?i)unauthori[sz]ed|forbidden|invalid api key|invalid token|bad credentials",
+                )
+                .expect("auth failure pattern is valid")
+            })
+            .is_match(&self.body)
+    }
+}
+
+/// Classify everything but a 2xx answer the provider gave to `noun` (e.g.
+/// "key", "token").
+fn screen(provider: &str, noun: &str, resp: &ProbeResponse) -> Result<(), SecretValidation> {
+    if resp.is_cloudflare_challenge() {
+        return Err(SecretValidation::network_error(format!(
+            "{provider} verification was blocked by Cloudflare"
+        )));
+    }
+    if resp.status == 429 {
+        return Err(SecretValidation::new(
+            ValidationStatus::RateLimited,
+            format!("{provider} rate limited the check; try again later"),
+        ));
+    }
+    if resp.is_auth_failure() {
+        return Err(SecretValidation::invalid(format!(
+            "{provider} rejected this {noun}"
+        )));
+    }
+    if resp.status >= 500 {
+        return Err(SecretValidation::network_error(format!(
+            "{provider} probe failed ({})",
+            resp.status
+        )));
+    }
+    if !resp.is_success() {
+        return Err(SecretValidation::invalid(format!(
+            "{provider} probe failed ({})",
+            resp.status
+        )));
+    }
+    Ok(())
+}
+
+fn relay_to_http_url(raw: &str) -> Option<Url> {
+    let mut url = Url::parse(raw).ok()?;
+    let scheme = match url.scheme() {
+        "ws" => "http",
+        "wss" => "https",
+        other => other,
+    }
+    .to_string();
+    url.set_scheme(&scheme).ok()?;
+    Some(url)
+}
+
+pub(crate) struct SecretValidator {
+    client: reqwest::Client,
+    /// Replaces the scheme, host and port of every provider URL; for tests.
+    origin: Option<Url>,
+}
+
+impl SecretValidator {
+    pub(crate) fn new() -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .use_native_tls()
+            .user_agent(USER_AGENT)
+            .timeout(PROBE_TIMEOUT)
+            .build()
+            .map_err(|e| format!("HTTP client error: {e}"))?;
+        Ok(SecretValidator {
+            client,
+            origin: None,
+        })
+    }
+
+    #[cfg(test)]
+    fn with_origin(origin: &str) -> Self {
+        let mut validator = SecretValidator::new().unwrap();
+        validator.origin = Some(Url::parse(origin).unwrap());
+        validator
+    }
+
+    fn endpoint(&self, url: &str) -> Url {
+        let mut url = Url::parse(url).expect("provider URLs are valid");
+        if let Some(origin) = &self.origin {
+            let _ = url.set_scheme(origin.scheme());
+            let _ = url.set_host(origin.host_str());
+            let _ = url.set_port(origin.port());
+        }
+        url
+    }
+
+    async fn send(
+        &self,
+        provider: &str,
+        request: RequestBuilder,
+    ) -> Result<ProbeResponse, SecretValidation> {
+        // Some providers take the key in the URL, so leave it out.
+        let unreachable = |e: reqwest::Error| {
+            SecretValidation::network_error(format!(
+                "Could not reach {provider}: {}",
+                e.without_url()
+            ))
+        };
+        let resp = request.send().await.map_err(unreachable)?;
+        let header = |name: &str| {
+            resp.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_ascii_lowercase)
+        };
+        let status = resp.status().as_u16();
+        let cf_ray = header("cf-ray").is_some();
+        let content_type = header("content-type").unwrap_or_default();
+        let body = resp.text().await.map_err(unreachable)?;
+        Ok(ProbeResponse {
+            status,
+            cf_ray,
+            content_type,
+            body,
+        })
+    }
+
+    /// Probes whose only check is a 2xx answer.
+    async fn expect_success(&self, provider: &str, noun: &str, request: RequestBuilder) -> Outcome {
+        let resp = self.send(provider, request).await?;
+        screen(provider, noun, &resp)?;
+        Ok(format!("{provider} {noun} verified"))
+    }
+
+    /// Check `value` for `key`. `stored` supplies the other half of paired
+    /// credentials (the OpenSky client id and secret).
+    pub(crate) async fn validate(
+        &self,
+        key: &str,
+        value: &str,
+        stored: &HashMap<String, String>,
+    ) -> SecretValidation {
+        let value = value.trim();
+        if value.is_empty() {
+            return SecretValidation::invalid("Value is required");
+        }
+        match self.probe(key, value, stored).await {
+            Ok(message) => SecretValidation::new(ValidationStatus::Ok, message),
+            Err(outcome) => outcome,
+        }
+    }
+
+    async fn probe(&self, key: &str, value: &str, stored: &HashMap<String, String>) -> Outcome {
+        let get = |url: Url| self.client.get(url).header("Accept", "application/json");
+        match key {
+            "GROQ_API_KEY" => {
+                let url = self.endpoint("https://api.groq.com/openai/v1/models");
+                self.expect_success("Groq", "key", get(url).bearer_auth(value))
+                    .await
+            }
+            "OPENROUTER_API_KEY" => {
+                let url = self.endpoint("https://openrouter.ai/api/v1/models");
+                self.expect_success("OpenRouter", "key", get(url).bearer_auth(value))
+                    .await
+            }
+            "ACLED_ACCESS_TOKEN" => {
+                let url =
+                    self.endpoint("https://acleddata.com/api/acled/read?_format=json&limit=1");
+                self.expect_success("ACLED", "token", get(url).bearer_auth(value))
+                    .await
+            }
+            "URLHAUS_AUTH_KEY" => {
+                let url = self.endpoint("https://urlhaus-api.abuse.ch/v1/urls/recent/limit/1/");
+                self.expect_success("URLhaus", "key", get(url).header("Auth-Key", value))
+                    .await
+            }
+            "OTX_API_KEY" => {
+                let url = self.endpoint("https://otx.alienvault.com/api/v1/user/me");
+                self.expect_success("OTX", "key", get(url).header("X-OTX-API-KEY", value))
+                    .await
+            }
+            "ABUSEIPDB_API_KEY" => {
+                let url = self.endpoint(
+                    "https://api.abuseipdb.com/api/v2/check?ipAddress=8.8.8.8&maxAgeInDays=90",
+                );
+                self.expect_success("AbuseIPDB", "key", get(url).header("Key", value))
+                    .await
+            }
+            "CLOUDFLARE_API_TOKEN" => {
+                let url = self.endpoint(
+                    "https://api.cloudflare.com/client/v4/radar/annotations/outages?dateRange=1d&limit=1",
+                );
+                let resp = self.send("Cloudflare", get(url).bearer_auth(value)).await?;
+                screen("Cloudflare", "token", &resp)?;
+                if resp.json()["success"] != Value::Bool(true) {
+                    return Err(SecretValidation::invalid(
+                        "Cloudflare Radar API did not return success",
+                    ));
+                }
+                Ok("Cloudflare token verified".to_string())
+            }
+            "FRED_API_KEY" => {
+                let mut url = self.endpoint(
+                    "https://api.stlouisfed.org/fred/series?series_id=GDP&file_type=json",
+                );
+                url.query_pairs_mut().append_pair("api_key", value);
+                let resp = self.send("FRED", get(url)).await?;
+                // FRED answers a bad key with a 400 and an error payload, but
+                // throttled and failed requests carry one too.
+                let payload = resp.json();
+                let rejected =
+                    !payload["error_code"].is_null() || !payload["error_message"].is_null();
+                if rejected && resp.status != 429 && resp.status < 500 {
+                    return Err(SecretValidation::invalid("FRED rejected this key"));
+                }
+                screen("FRED", "key", &resp)?;
+                if !payload["seriess"].is_array() {
+                    return Err(SecretValidation::invalid("Unexpected FRED response"));
+                }
+                Ok("FRED key verified".to_string())
+            }
+            "EIA_API_KEY" => {
+                let mut url = self.endpoint("https://api.eia.gov/v2/");
+                url.query_pairs_mut().append_pair("api_key", value);
+                let resp = self.send("EIA", get(url)).await?;
+                screen("EIA", "key", &resp)?;
+                let payload = resp.json();
+                if payload["response"]["id"].is_null() && payload["response"]["routes"].is_null() {
+                    return Err(SecretValidation::invalid("Unexpected EIA response"));
+                }
+                Ok("EIA key verified".to_string())
+            }
+            "FINNHUB_API_KEY" => {
+                let mut url = self.endpoint("https://finnhub.io/api/v1/quote?symbol=AAPL");
+                url.query_pairs_mut().append_pair("token", value);
+                let resp = self.send("Finnhub", get(url)).await?;
+                screen("Finnhub", "key", &resp)?;
+                let payload = resp.json();
+                let error = payload["error"].as_str().unwrap_or_default();
+                if error.to_ascii_lowercase().contains("invalid") {
+                    return Err(SecretValidation::invalid("Finnhub rejected this key"));
+                }
+                if !payload["c"].is_number() {
+                    return Err(SecretValidation::invalid("Unexpected Finnhub response"));
+                }
+                Ok("Finnhub key verified".to_string())
+            }
+            "NASA_FIRMS_API_KEY" => {
+                let mut url = self.endpoint("https://firms.modaps.eosdis.nasa.gov/api/area/csv");
+                url.path_segments_mut()
+                    .expect("FIRMS URL has a path")
+                    .extend([value, "VIIRS_SNPP_NRT", "22,44,40,53", "1"]);
+                let resp = self
+                    .send(
+                        "NASA FIRMS",
+                        self.client.get(url).header("Accept", "text/csv"),
+                    )
+                    .await?;
+                screen("NASA FIRMS", "key", &resp)?;
+                static REJECTED: OnceLock<Regex> = OnceLock::new();
+                let rejected = REJECTED.get_or_init(|| {
+                    Regex::new(r"(?i)invalid api key|not authorized|forbidden")
+                        .expect("FIRMS rejection pattern is valid")
+                });
+                if rejected.is_match(&resp.body) {
+                    return Err(SecretValidation::invalid("NASA FIRMS rejected this key"));
+                }
+                Ok("NASA FIRMS key verified".to_string())
+            }
+            "WINGBITS_API_KEY" => {
+                let url =
+                    self.endpoint("https://customer-api.wingbits.com/v1/flights/details/3c6444");
+                let resp = self
+                    .send("Wingbits", get(url).header("x-api-key", value))
+                    .await?;
+                // Any other 4xx (e.g. an unknown flight) still means the key was accepted.
+                if let Err(outcome) = screen("Wingbits", "key", &resp) {
+                    if outcome.status != ValidationStatus::Invalid || resp.is_auth_failure() {
+                        return Err(outcome);
+                    }
+                }
+                Ok("Wingbits key accepted".to_string())
+            }
+            "OPENSKY_CLIENT_ID" | "OPENSKY_CLIENT_SECRET" => {
+                let other = |name: &str| stored.get(name).map(|v| v.trim()).unwrap_or_default();
+                let (client_id, client_secret) = if key == "OPENSKY_CLIENT_ID" {
+                    (value, other("OPENSKY_CLIENT_SECRET"))
+                } else {
+                    (other("OPENSKY_CLIENT_ID"), value)
+                };
+                if client_id.is_empty() || client_secret.is_empty() {
+                    return Err(SecretValidation::invalid(
+                        "Set both OPENSKY_CLIENT_ID and OPENSKY_CLIENT_SECRET before verification",
+                    ));
+                }
+                let url = self.endpoint(
+                    "https://auth.opensky-network.org/auth/realms/opensky-network/protocol/openid-connect/token",
+                );
+                let request = self.client.post(url).form(&[
+                    ("grant_type", "client_credentials"),
+                    ("client_id", client_id),
+                    ("client_secret", client_secret),
+                ]);
+                let resp = self.send("OpenSky", request).await?;
+                screen("OpenSky", "credentials", &resp)?;
+                if resp.json()["access_token"].is_null() {
+                    return Err(SecretValidation::invalid(
+                        "OpenSky auth response did not include an access token",
+                    ));
+                }
+                Ok("OpenSky credentials verified".to_string())
+            }
+            "OLLAMA_API_URL" => {
+                let base = match Url::parse(value) {
+                    Ok(url) if matches!(url.scheme(), "http" | "https") => url,
+                    Ok(_) => return Err(SecretValidation::invalid("Must be an http(s) URL")),
+                    Err(_) => return Err(SecretValidation::invalid("Invalid URL")),
+                };
+                // The OpenAI-compatible endpoint first, then the native one.
+                let mut last = None;
+                for path in ["/v1/models", "/api/tags"] {
+                    let url = base
+                        .join(path)
+                        .map_err(|_| SecretValidation::invalid("Invalid URL"))?;
+                    let request = self.client.get(url).timeout(OLLAMA_PROBE_TIMEOUT);
+                    let resp = self.send("Ollama", request).await?;
+                    if resp.is_success() {
+                        return Ok("Ollama endpoint verified".to_string());
+                    }
+                    last = Some(resp.status);
+                }
+                Err(SecretValidation::network_error(format!(
+                    "Ollama probe failed ({})",
+                    last.unwrap_or_default()
+                )))
+            }
+            "WS_RELAY_URL" | "VITE_WS_RELAY_URL" | "VITE_OPENSKY_RELAY_URL" => {
+                let url = relay_to_http_url(value)
+                    .ok_or_else(|| SecretValidation::invalid("Relay URL is invalid"))?;
+                let resp = self.send("the relay", self.client.get(url)).await?;
+                if resp.status >= 500 {
+                    return Err(SecretValidation::network_error(format!(
+                        "Relay probe failed ({})",
+                        resp.status
+                    )));
+                }
+                Ok("Relay URL is reachable".to_string())
+            }
+            "OLLAMA_MODEL" => Err(SecretValidation::new(
+                ValidationStatus::Unchecked,
+                "Model name stored",
+            )),
+            _ => Err(SecretValidation::new(
+                ValidationStatus::Unchecked,
+                "Key stored (no live check available)",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::serve;
+    use std::net::{Ipv4Addr, TcpListener};
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn classifies_provider_answers() {
+        let stored = HashMap::new();
+        let (port, server) = serve(&[
+            (200, "", r#"{"data":[]}"#),
+            (401, "", r#"{"error":"invalid"}"#),
+            (429, "", ""),
+            (200, "", r#"{"success":false}"#),
+            (
+                403,
+                "cf-ray: 1\r\nContent-Type: text/html\r\n",
+                "<html><title>Attention Required</title>Ray ID: 1</html>",
+            ),
+            (200, "", "Invalid API key."),
+            (
+                400,
+                "",
+                r#"{"error_code":400,"error_message":"Bad api_key"}"#,
+            ),
+            (
+                429,
+                "",
+                r#"{"error_code":429,"error_message":"Too Many Requests"}"#,
+            ),
+            (404, "", r#"{"error":"not found"}"#),
+        ]);
+        let validator = SecretValidator::with_origin(&format!("http://127.0.0.1:{port}"));
+        let check = |key: &str, value: &str| block_on(validator.validate(key, value, &stored));
+
+        let ok = check("GROQ_API_KEY", "gsk_1");
+        assert_eq!(ok.status, ValidationStatus::Ok);
+        assert_eq!(
+            check("OPENROUTER_API_KEY", "sk-bad").status,
+            ValidationStatus::Invalid
+        );
+        assert_eq!(
+            check("FINNHUB_API_KEY", "a b").status,
+            ValidationStatus::RateLimited
+        );
+        assert_eq!(
+            check("CLOUDFLARE_API_TOKEN", "t").status,
+            ValidationStatus::Invalid
+        );
+        assert_eq!(
+            check("OTX_API_KEY", "k").status,
+            ValidationStatus::NetworkError
+        );
+        assert_eq!(
+            check("NASA_FIRMS_API_KEY", "k/1").status,
+            ValidationStatus::Invalid
+        );
+        assert_eq!(check("FRED_API_KEY", "k").status, ValidationStatus::Invalid);
+        assert_eq!(
+            check("FRED_API_KEY", "k").status,
+            ValidationStatus::RateLimited
+        );
+        assert_eq!(check("WINGBITS_API_KEY", "k").status, ValidationStatus::Ok);
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /openai/v1/models"));
+        assert!(requests[0]
+            .to_ascii_lowercase()
+            .contains("authorization: bearer gsk_1"));
+        assert!(requests[2].contains("token=a+b"));
+        assert!(requests[4]
+            .to_ascii_lowercase()
+            .contains("x-otx-api-key: k"));
+        assert!(requests[5].starts_with("GET /api/area/csv/k%2F1/VIIRS_SNPP_NRT/"));
+
+        assert_eq!(
+            check("GROQ_API_KEY", "  ").status,
+            ValidationStatus::Invalid
+        );
+        assert_eq!(
+            check("OPENSKY_CLIENT_ID", "id").status,
+            ValidationStatus::Invalid
+        );
+        assert_eq!(
+            check("WTO_API_KEY", "k").status,
+            ValidationStatus::Unchecked
+        );
+    }
+
+    #[test]
+    fn unreachable_provider_is_a_network_error() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        let validator = SecretValidator::with_origin(&format!("http://127.0.0.1:{port}"));
+        let result = block_on(validator.validate("GROQ_API_KEY", "gsk_1", &HashMap::new()));
+        assert_eq!(result.status, ValidationStatus::NetworkError);
+        assert!(result.message.contains("Groq"));
+
+        let fred = block_on(validator.validate("FRED_API_KEY", "fredsecret123", &HashMap::new()));
+        assert_eq!(fred.status, ValidationStatus::NetworkError);
+        assert!(!fred.message.contains("fredsecret123"));
+        let firms =
+            block_on(validator.validate("NASA_FIRMS_API_KEY", "firmssecret", &HashMap::new()));
+        assert!(!firms.message.contains("firmssecret"));
+
+        let ollama = block_on(validator.validate(
+            "OLLAMA_API_URL",
+            &format!("http://127.0.0.1:{port}"),
+            &HashMap::new(),
+        ));
+        assert_eq!(ollama.status, ValidationStatus::NetworkError);
+    }
+}
@@ -0,0 +1,466 @@
+//! Passphrase-encrypted copies of the secrets vault: exported backups, and
+//! the on-disk vault used on systems without a persistent OS keyring.
+//!
+//! A vault file is the secret map sealed with XChaCha20-Poly1305 under a key
+//! derived from the user's passphrase with Argon2id. The KDF parameters and
+//! salt are stored in the header and authenticated as associated data, so a
+//! file cannot be downgraded to cheaper parameters without failing to open.
+//!
+//! Layout: `MAGIC (4) || m_cost || t_cost || p_cost (u32 LE each) ||
+//! salt (16) || nonce (24) || ciphertext+tag`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+
+pub(crate) const EXPORT_MAGIC: &[u8; 4] = b"WMV1";
+pub(crate) const EXPORT_EXTENSION: &str = "wmvault";
+/// Magic of the on-disk vault used when there is no OS keyring.
+const FILE_MAGIC: &[u8; 4] = b"WMF1";
+pub(crate) const FILE_NAME: &str = "secrets-vault.bin";
+pub(crate) const MIN_PASSPHRASE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = 4 + 3 * 4 + SALT_LEN + NONCE_LEN;
+/// Parameters outside these bounds are refused on open, so a crafted file
+/// cannot make the KDF allocate gigabytes or spin for minutes.
+const MAX_M_COST_KIB: u32 = 256 * 1024;
+const MAX_T_COST: u32 = 10;
+const MAX_P_COST: u32 = 8;
+
+/// Argon2id cost parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct KdfParams {
+    pub m_cost_kib: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            m_cost_kib: 64 * 1024,
+            t_cost: 3,
+            p_cost: 1,
+        }
+    }
+}
+
+/// A passphrase-derived key together with the parameters and salt it was
+/// derived with, so a file can be resealed without rerunning the KDF.
+struct SealKey {
+    params: KdfParams,
+    salt: [u8; SALT_LEN],
+    key: [u8; 32],
+}
+
+impl SealKey {
+    fn derive(passphrase: &str, salt: [u8; SALT_LEN], params: KdfParams) -> Result<Self, String> {
+        let argon_params = Params::new(params.m_cost_kib, params.t_cost, params.p_cost, Some(32))
+            .map_err(|e| format!("Invalid key derivation parameters: {e}"))?;
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, argon_params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| format!("Key derivation failed: {e}"))?;
+        Ok(SealKey { params, salt, key })
+    }
+
+    fn generate(passphrase: &str, params: KdfParams) -> Result<Self, String> {
+        let mut salt = [0u8; SALT_LEN];
+        getrandom::getrandom(&mut salt).expect("OS CSPRNG unavailable");
+        SealKey::derive(passphrase, salt, params)
+    }
+
+    fn seal(&self, magic: &[u8; 4], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        getrandom::getrandom(&mut nonce).expect("OS CSPRNG unavailable");
+
+        let mut out = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+        out.extend_from_slice(magic);
+        out.extend_from_slice(&self.params.m_cost_kib.to_le_bytes());
+        out.extend_from_slice(&self.params.t_cost.to_le_bytes());
+        out.extend_from_slice(&self.params.p_cost.to_le_bytes());
+        out.extend_from_slice(&self.salt);
+        out.extend_from_slice(&nonce);
+
+        let ciphertext = XChaCha20Poly1305::new(&self.key.into())
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &out,
+                },
+            )
+            .map_err(|_| "Vault encryption failed".to_string())?;
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    /// Decrypt a file whose header has already been checked by [`read_header`].
+    fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        let (header, ciphertext) = sealed.split_at(HEADER_LEN);
+        XChaCha20Poly1305::new(&self.key.into())
+            .decrypt(
+                XNonce::from_slice(&header[16 + SALT_LEN..]),
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| "Wrong passphrase or corrupted vault file".to_string())
+    }
+}
+
+/// Seal `plaintext` under `passphrase`, tagging the file with `magic`.
+pub(crate) fn seal(
+    magic: &[u8; 4],
+    passphrase: &str,
+    plaintext: &[u8],
+    params: KdfParams,
+) -> Result<Vec<u8>, String> {
+    SealKey::generate(passphrase, params)?.seal(magic, plaintext)
+}
+
+/// Inverse of [`seal`], also returning the derived key. A wrong passphrase
+/// and a tampered file are indistinguishable and report the same error.
+fn open_with_key(
+    magic: &[u8; 4],
+    passphrase: &str,
+    sealed: &[u8],
+) -> Result<(Vec<u8>, SealKey), String> {
+    let (params, salt) = read_header(magic, sealed)?;
+    let key = SealKey::derive(passphrase, salt, params)?;
+    let plaintext = key.open(sealed)?;
+    Ok((plaintext, key))
+}
+
+/// The KDF parameters and salt from a sealed file's header.
+fn read_header(magic: &[u8; 4], sealed: &[u8]) -> Result<(KdfParams, [u8; SALT_LEN]), String> {
+    if sealed.len() < HEADER_LEN || !sealed.starts_with(magic) {
+        return Err("Not a World Monitor vault file".to_string());
+    }
+    let word = |at: usize| u32::from_le_bytes(sealed[at..at + 4].try_into().expect("4 bytes"));
+    let params = KdfParams {
+        m_cost_kib: word(4),
+        t_cost: word(8),
+        p_cost: word(12),
+    };
+    if params.m_cost_kib > MAX_M_COST_KIB
+        || params.t_cost > MAX_T_COST
+        || params.p_cost > MAX_P_COST
+    {
+        return Err("Vault file uses unsupported key derivation parameters".to_string());
+    }
+    let salt = sealed[16..16 + SALT_LEN].try_into().expect("salt length");
+    Ok((params, salt))
+}
+
+/// Create `path` readable by the current user only (on Unix).
+pub(crate) fn write_private_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(bytes))
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// The secrets vault as a passphrase-sealed file, used where the OS has no
+/// persistent keyring. Locked until [`FileVault::unlock`] succeeds; the
+/// derived key is then kept in memory so saves do not prompt again.
+pub(crate) struct FileVault {
+    path: PathBuf,
+    params: KdfParams,
+    key: Option<SealKey>,
+}
+
+impl FileVault {
+    pub(crate) fn new(path: PathBuf, params: KdfParams) -> Self {
+        FileVault {
+            path,
+            params,
+            key: None,
+        }
+    }
+
+    pub(crate) fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    pub(crate) fn is_unlocked(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Open the vault with `passphrase` and return its contents. When there
+    /// is no vault file yet, one holding an empty JSON object is created
+    /// under `passphrase`.
+    pub(crate) fn unlock(&mut self, passphrase: &str) -> Result<Vec<u8>, String> {
+        if !self.exists() {
+            if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+                return Err(format!(
+                    "Passphrase must be at least {MIN_PASSPHRASE_LEN} characters"
+                ));
+            }
+            let key = SealKey::generate(passphrase, self.params)?;
+            self.write(&key, b"{}")?;
+            self.key = Some(key);
+            return Ok(b"{}".to_vec());
+        }
+        let sealed = fs::read(&self.path)
+            .map_err(|e| format!("Failed to read {}: {e}", self.path.display()))?;
+        let (contents, key) = open_with_key(FILE_MAGIC, passphrase, &sealed)?;
+        self.key = Some(key);
+        Ok(contents)
+    }
+
+    /// Forget the derived key; saves fail until the next unlock.
+    pub(crate) fn lock(&mut self) {
+        self.key = None;
+    }
+
+    pub(crate) fn save(&self, contents: &[u8]) -> Result<(), String> {
+        let key = self
+            .key
+            .as_ref()
+            .ok_or_else(|| "Secrets vault is locked; unlock it in Settings".to_string())?;
+        self.write(key, contents)
+    }
+
+    /// Seal to a temporary file and rename it over the vault, so a crash
+    /// mid-write never leaves a truncated vault behind.
+    fn write(&self, key: &SealKey, contents: &[u8]) -> Result<(), String> {
+        let sealed = key.seal(FILE_MAGIC, contents)?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        write_private_file(&tmp, &sealed)?;
+        fs::rename(&tmp, &self.path)
+            .map_err(|e| format!("Failed to replace {}: {e}", self.path.display()))
+    }
+}
+
+/// Decrypted contents of an exported vault.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VaultExport {
+    pub app_version: String,
+    pub created_at: i64,
+    pub secrets: BTreeMap<String, String>,
+}
+
+pub(crate) fn encode_export(
+    export: &VaultExport,
+    passphrase: &str,
+    params: KdfParams,
+) -> Result<Vec<u8>, String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!(
+            "Passphrase must be at least {MIN_PASSPHRASE_LEN} characters"
+        ));
+    }
+    let json = serde_json::to_vec(export).map_err(|e| format!("Failed to serialize vault: {e}"))?;
+    seal(EXPORT_MAGIC, passphrase, &json, params)
+}
+
+/// The key an export was opened with, kept so the same file can be read
+/// again after the preview without rerunning the KDF.
+pub(crate) struct ExportKey(SealKey);
+
+pub(crate) fn decode_export_with_key(
+    bytes: &[u8],
+    passphrase: &str,
+) -> Result<(VaultExport, ExportKey), String> {
+    let (json, key) = open_with_key(EXPORT_MAGIC, passphrase, bytes)?;
+    Ok((parse_export(&json)?, ExportKey(key)))
+}
+
+/// Reopen an export with the key [`decode_export_with_key`] derived for it.
+/// Fails if the file was replaced by one sealed under another salt.
+pub(crate) fn decode_export_using(bytes: &[u8], key: &ExportKey) -> Result<VaultExport, String> {
+    let (params, salt) = read_header(EXPORT_MAGIC, bytes)?;
+    if params != key.0.params || salt != key.0.salt {
+        return Err("Vault file changed since it was previewed".to_string());
+    }
+    parse_export(&key.0.open(bytes)?)
+}
+
+fn parse_export(json: &[u8]) -> Result<VaultExport, String> {
+    serde_json::from_slice(json).map_err(|e| format!("Vault file is malformed: {e}"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum MergeStatus {
+    /// Not set locally.
+    Added,
+    /// Set locally to a different value.
+    Changed,
+    Unchanged,
+    /// Not a key this build accepts; never imported.
+    Unsupported,
+}
+
+/// One row of the import preview. Values are never included.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MergePreviewItem {
+    pub key: String,
+    pub status: MergeStatus,
+}
+
+/// How each key in `incoming` would combine with `current`, sorted by key.
+pub(crate) fn preview_merge(
+    current: &HashMap<String, String>,
+    incoming: &BTreeMap<String, String>,
+    supported: &[&str],
+) -> Vec<MergePreviewItem> {
+    incoming
+        .iter()
+        .filter(|(_, value)| !value.trim().is_empty())
+        .map(|(key, value)| {
+            let status = if !supported.contains(&key.as_str()) {
+                MergeStatus::Unsupported
+            } else {
+                match current.get(key) {
+                    None => MergeStatus::Added,
+                    Some(existing) if existing == value.trim() => MergeStatus::Unchanged,
+                    Some(_) => MergeStatus::Changed,
+                }
+            };
+            MergePreviewItem {
+                key: key.clone(),
+                status,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    const FAST: KdfParams = KdfParams {
+        m_cost_kib: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    fn decode_export(bytes: &[u8], passphrase: &str) -> Result<VaultExport, String> {
+        decode_export_with_key(bytes, passphrase).map(|(export, _)| export)
+    }
+
+    #[test]
+    fn export_round_trips_and_rejects_wrong_passphrase_or_tampering() {
+        let export = VaultExport {
+            app_version: "2.5.8".into(),
+            created_at: 7,
+            secrets: BTreeMap::from([("GROQ_API_KEY".into(), "gsk-1".into())]),
+        };
+        assert!(encode_export(&export, "short", FAST).is_err());
+
+        let bytes = encode_export(&export, "correct horse battery", FAST).unwrap();
+        let decoded = decode_export(&bytes, "correct horse battery").unwrap();
+        assert_eq!(decoded.secrets, export.secrets);
+        assert!(decode_export(&bytes, "wrong horse battery")
+            .unwrap_err()
+            .contains("Wrong passphrase"));
+
+        // Lowering the KDF cost in the header must break authentication.
+        let mut downgraded = bytes.clone();
+        downgraded[4] ^= 0x01;
+        assert!(decode_export(&downgraded, "correct horse battery").is_err());
+        assert!(decode_export(b"WMC1 not a vault", "x").is_err());
+    }
+
+    #[test]
+    fn previewed_export_reopens_with_its_key_only() {
+        let export = VaultExport {
+            app_version: "2.5.8".into(),
+            created_at: 7,
+            secrets: BTreeMap::from([("GROQ_API_KEY".into(), "gsk-1".into())]),
+        };
+        let bytes = encode_export(&export, "correct horse battery", FAST).unwrap();
+        let (_, key) = decode_export_with_key(&bytes, "correct horse battery").unwrap();
+        assert_eq!(
+            decode_export_using(&bytes, &key).unwrap().secrets,
+            export.secrets
+        );
+
+        let resealed = encode_export(&export, "correct horse battery", FAST).unwrap();
+        assert!(decode_export_using(&resealed, &key)
+            .unwrap_err()
+            .contains("changed"));
+        let mut tampered = bytes.clone();
+        *tampered.last_mut().unwrap() ^= 0x01;
+        assert!(decode_export_using(&tampered, &key).is_err());
+    }
+
+    #[test]
+    fn file_vault_is_created_on_first_unlock_and_reopens() {
+        let dir = temp_dir("file-vault");
+        // The first save creates the missing app data dir.
+        let path = dir.join("app-data").join(FILE_NAME);
+
+        let mut vault = FileVault::new(path.clone(), FAST);
+        assert!(vault.save(b"{}").unwrap_err().contains("locked"));
+        assert!(vault.unlock("short").is_err());
+        assert!(!vault.exists());
+        assert_eq!(vault.unlock("correct horse battery").unwrap(), b"{}");
+        vault.save(br#"{"GROQ_API_KEY":"gsk-1"}"#).unwrap();
+
+        let mut reopened = FileVault::new(path.clone(), FAST);
+        assert!(!reopened.is_unlocked());
+        assert!(reopened.unlock("wrong horse battery").is_err());
+        assert!(!reopened.is_unlocked());
+        assert_eq!(
+            reopened.unlock("correct horse battery").unwrap(),
+            br#"{"GROQ_API_KEY":"gsk-1"}"#
+        );
+        // Exported backups and the on-disk vault are not interchangeable.
+        assert!(decode_export(&fs::read(&path).unwrap(), "correct horse battery").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn preview_classifies_each_incoming_key() {
+        let current = HashMap::from([
+            ("GROQ_API_KEY".to_string(), "same".to_string()),
+            ("FRED_API_KEY".to_string(), "old".to_string()),
+        ]);
+        let incoming = BTreeMap::from([
+            ("GROQ_API_KEY".to_string(), "same".to_string()),
+            ("FRED_API_KEY".to_string(), "new".to_string()),
+            ("EIA_API_KEY".to_string(), "x".to_string()),
+            ("ROGUE_KEY".to_string(), "x".to_string()),
+            ("OTX_API_KEY".to_string(), "  ".to_string()),
+        ]);
+        let supported = ["GROQ_API_KEY", "FRED_API_KEY", "EIA_API_KEY", "OTX_API_KEY"];
+        let statuses: Vec<(String, MergeStatus)> = preview_merge(&current, &incoming, &supported)
+            .into_iter()
+            .map(|item| (item.key, item.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("EIA_API_KEY".into(), MergeStatus::Added),
+                ("FRED_API_KEY".into(), MergeStatus::Changed),
+                ("GROQ_API_KEY".into(), MergeStatus::Unchanged),
+                ("ROGUE_KEY".into(), MergeStatus::Unsupported),
+            ]
+        );
+    }
+}
@@ -0,0 +1,45 @@
+//! Helpers shared by the unit tests of several modules.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A fresh, empty directory under the system temp dir, unique to this run.
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!("wm-test-{name}-{}-{nanos}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Answer each of `responses` (status, extra headers, body) in turn on a
+/// fresh loopback port and hand back the port and the raw requests received.
+pub(crate) fn serve(
+    responses: &'static [(u16, &'static str, &'static str)],
+) -> (u16, thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = thread::spawn(move || {
+        responses
+            .iter()
+            .map(|(status, headers, body)| {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = vec![0u8; 8192];
+                let n = stream.read(&mut buf).unwrap();
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status} X\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                String::from_utf8_lossy(&buf[..n]).into_owned()
+            })
+            .collect()
+    });
+    (port, handle)
+}
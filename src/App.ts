@@ -21,6 +21,7 @@ import { preloadCountryGeometry, getCountryNameByCode } from '@/services/country
 import { initI18n } from '@/services/i18n';
 
 import { DesktopUpdater } from '@/app/desktop-updater';
+import { LocalApiStatusBanner } from '@/app/local-api-status';
 import { CountryIntelManager } from '@/app/country-intel';
 import { SearchManager } from '@/app/search-manager';
 import { RefreshScheduler } from '@/app/refresh-scheduler';
@@ -43,6 +44,7 @@ export class App {
   private countryIntel: CountryIntelManager;
   private refreshScheduler: RefreshScheduler;
   private desktopUpdater: DesktopUpdater;
+  private localApiStatus: LocalApiStatusBanner;
 
   private modules: { destroy(): void }[] = [];
 
@@ -247,6 +249,7 @@ export class App {
     this.refreshScheduler = new RefreshScheduler(this.state);
     this.countryIntel = new CountryIntelManager(this.state);
     this.desktopUpdater = new DesktopUpdater(this.state);
+    this.localApiStatus = new LocalApiStatusBanner(this.state);
 
     this.dataLoader = new DataLoaderManager(this.state, {
       renderCriticalBanner: (postures) => this.panelLayout.renderCriticalBanner(postures),
@@ -278,6 +281,7 @@ export class App {
     // Track destroy order (reverse of init)
     this.modules = [
       this.desktopUpdater,
+      this.localApiStatus,
       this.panelLayout,
       this.countryIntel,
       this.searchManager,
@@ -374,6 +378,7 @@ export class App {
     // Phase 8: Deep links + update checks
     this.handleDeepLinks();
     this.desktopUpdater.init();
+    this.localApiStatus.init();
 
     // Analytics
     trackEvent('wm_app_loaded', {
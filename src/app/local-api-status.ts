@@ -0,0 +1,85 @@
+import type { AppContext, AppModule } from '@/app/app-context';
+import { listenTauri, tryInvokeTauri } from '@/services/tauri-bridge';
+import { t } from '@/services/i18n';
+import { escapeHtml } from '@/utils/sanitize';
+
+/** Payload of the `local-api-status` event the desktop shell sends this window. */
+interface LocalApiStatus {
+  state: 'running' | 'restarting' | 'failed';
+  reason: string;
+  attempt: number;
+  retryInMs: number;
+  port: number;
+}
+
+/**
+ * Shows a banner while the local API sidecar is being restarted, or after the
+ * supervisor gave up on it. The new port is picked up by `runtime.ts`, which
+ * listens for the same event.
+ */
+export class LocalApiStatusBanner implements AppModule {
+  private ctx: AppContext;
+  private unlisten: (() => void) | null = null;
+  private banner: HTMLElement | null = null;
+
+  constructor(ctx: AppContext) {
+    this.ctx = ctx;
+  }
+
+  init(): void {
+    if (!this.ctx.isDesktopApp) return;
+    void listenTauri<LocalApiStatus>('local-api-status', (status) => this.render(status))
+      .then((unlisten) => {
+        if (this.ctx.isDestroyed) unlisten();
+        else this.unlisten = unlisten;
+      })
+      .catch((error) => console.warn('[local-api] status listener unavailable', error));
+  }
+
+  destroy(): void {
+    this.unlisten?.();
+    this.unlisten = null;
+    this.hide();
+  }
+
+  private render(status: LocalApiStatus): void {
+    if (status.state === 'running') {
+      this.hide();
+      return;
+    }
+
+    const failed = status.state === 'failed';
+    const title = failed ? t('modals.localApi.failedTitle') : t('modals.localApi.restartingTitle');
+    const detail = failed
+      ? t('modals.localApi.failedDetail', { reason: status.reason })
+      : t('modals.localApi.restartingDetail', {
+        attempt: status.attempt,
+        seconds: Math.ceil(status.retryInMs / 1000),
+      });
+
+    const banner = this.banner ?? document.createElement('div');
+    banner.className = `local-api-banner${failed ? ' failed' : ''}`;
+    banner.innerHTML = `
+      <div class="local-api-banner-body">
+        <div class="local-api-banner-title">${escapeHtml(title)}</div>
+        <div class="local-api-banner-detail">${escapeHtml(detail)}</div>
+      </div>
+      ${failed ? `<button class="local-api-banner-action" data-action="log">${escapeHtml(t('modals.localApi.viewLog'))}</button>` : ''}
+      <button class="local-api-banner-dismiss" data-action="dismiss" aria-label="${escapeHtml(t('modals.localApi.dismiss'))}">×</button>
+    `;
+    if (!this.banner) {
+      banner.addEventListener('click', (e) => {
+        const action = (e.target as HTMLElement).closest<HTMLElement>('[data-action]')?.dataset.action;
+        if (action === 'log') void tryInvokeTauri<string>('open_sidecar_log_file');
+        else if (action === 'dismiss') this.hide();
+      });
+      document.body.appendChild(banner);
+      this.banner = banner;
+    }
+  }
+
+  private hide(): void {
+    this.banner?.remove();
+    this.banner = null;
+  }
+}
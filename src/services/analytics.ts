@@ -266,7 +266,7 @@ export function trackApiKeysSnapshot(): void {
   const presence: Record<string, boolean> = {};
   for (const [internalKey, analyticsName] of Object.entries(SECRET_ANALYTICS_NAMES)) {
     const state = config.secrets[internalKey as RuntimeSecretKey];
-    presence[`has_${analyticsName}`] = Boolean(state?.value);
+    presence[`has_${analyticsName}`] = Boolean(state);
   }
 
   const enabledFeatures = Object.entries(config.featureToggles)
@@ -1,6 +1,7 @@
 import { isDesktopRuntime } from './runtime';
-import { invokeTauri } from './tauri-bridge';
+import { invokeTauri, listenTauri } from './tauri-bridge';
 import { isStorageQuotaExceeded, isQuotaError, markStorageQuotaExceeded } from '@/utils';
+import { SITE_VARIANT } from '@/config/variant';
 
 type CacheEnvelope<T> = {
   key: string;
@@ -15,6 +16,112 @@ const CACHE_STORE = 'entries';
 
 let cacheDbPromise: Promise<IDBDatabase> | null = null;
 
+// Each variant has its own desktop cache store, so a build that switches
+// variant at runtime never reads another variant's panels.
+const DESKTOP_NAMESPACE = SITE_VARIANT;
+
+type Waiter<T> = { resolve: (value: T) => void; reject: (error: unknown) => void };
+
+type CacheWriteRefusal = { code: string; key: string; message: string; size?: number; limit?: number };
+
+/** The desktop cache refused a write (key not allowed, payload too large, ...). */
+export class CacheWriteRefusedError extends Error {
+  constructor(readonly refusal: CacheWriteRefusal) {
+    super(refusal.message);
+    this.name = 'CacheWriteRefusedError';
+  }
+}
+
+function cacheWriteRefusals(error: unknown): CacheWriteRefusal[] {
+  const refusals = (error as { refusals?: unknown } | null)?.refusals;
+  return Array.isArray(refusals) ? refusals as CacheWriteRefusal[] : [];
+}
+
+// Desktop reads/writes issued in the same tick are coalesced into a single
+// read_cache_entries / write_cache_entries IPC call, so startup hydration of
+// many panels costs one round-trip instead of one per panel.
+let pendingReads = new Map<string, Array<Waiter<unknown>>>();
+let pendingWrites = new Map<string, { value: string; waiters: Array<Waiter<void>> }>();
+let readFlushScheduled = false;
+let writeFlushScheduled = false;
+
+async function flushDesktopReads(): Promise<void> {
+  const batch = pendingReads;
+  pendingReads = new Map();
+  readFlushScheduled = false;
+  try {
+    const found = await invokeTauri<Record<string, unknown>>('read_cache_entries', {
+      namespace: DESKTOP_NAMESPACE,
+      keys: [...batch.keys()],
+    });
+    batch.forEach((waiters, key) => waiters.forEach(w => w.resolve(found?.[key] ?? null)));
+  } catch (error) {
+    batch.forEach(waiters => waiters.forEach(w => w.reject(error)));
+  }
+}
+
+async function flushDesktopWrites(): Promise<void> {
+  const batch = pendingWrites;
+  pendingWrites = new Map();
+  writeFlushScheduled = false;
+  const entries: Record<string, string> = {};
+  batch.forEach(({ value }, key) => { entries[key] = value; });
+  try {
+    await invokeTauri<void>('write_cache_entries', { namespace: DESKTOP_NAMESPACE, entries });
+    batch.forEach(({ waiters }) => waiters.forEach(w => w.resolve()));
+  } catch (error) {
+    const refusals = new Map(cacheWriteRefusals(error).map(r => [r.key, r]));
+    if (refusals.size === 0) {
+      batch.forEach(({ waiters }) => waiters.forEach(w => w.reject(error)));
+      return;
+    }
+    // A refusal rejects the whole batch on the Rust side; fail only the
+    // refused keys and resubmit the rest.
+    batch.forEach(({ value, waiters }, key) => {
+      const refusal = refusals.get(key);
+      if (refusal) waiters.forEach(w => w.reject(new CacheWriteRefusedError(refusal)));
+      else queueDesktopWrite(key, value, waiters, false);
+    });
+  }
+}
+
+function readDesktopEntry<T>(key: string): Promise<CacheEnvelope<T> | null> {
+  return new Promise((resolve, reject) => {
+    const waiters = pendingReads.get(key) ?? [];
+    waiters.push({ resolve: resolve as (value: unknown) => void, reject });
+    pendingReads.set(key, waiters);
+    if (!readFlushScheduled) {
+      readFlushScheduled = true;
+      setTimeout(() => void flushDesktopReads(), 0);
+    }
+  });
+}
+
+function queueDesktopWrite(
+  key: string,
+  value: string,
+  waiters: Array<Waiter<void>>,
+  replaceQueued = true,
+): void {
+  // A later write to the same key supersedes the queued one; every caller
+  // settles with the batch. Resubmitted writes never replace a newer value.
+  const queued = pendingWrites.get(key);
+  if (queued) {
+    queued.waiters.push(...waiters);
+    if (replaceQueued) queued.value = value;
+  } else {
+    pendingWrites.set(key, { value, waiters });
+  }
+  if (!writeFlushScheduled) {
+    writeFlushScheduled = true;
+    setTimeout(() => void flushDesktopWrites(), 0);
+  }
+}
+
+function writeDesktopEntry(key: string, value: string): Promise<void> {
+  return new Promise((resolve, reject) => queueDesktopWrite(key, value, [{ resolve, reject }]));
+}
+
 function isIndexedDbAvailable(): boolean {
   return typeof window !== 'undefined' && typeof window.indexedDB !== 'undefined';
 }
@@ -72,8 +179,7 @@ async function setInIndexedDb<T>(payload: CacheEnvelope<T>): Promise<void> {
 export async function getPersistentCache<T>(key: string): Promise<CacheEnvelope<T> | null> {
   if (isDesktopRuntime()) {
     try {
-      const value = await invokeTauri<CacheEnvelope<T> | null>('read_cache_entry', { key });
-      return value ?? null;
+      return await readDesktopEntry<T>(key);
     } catch (error) {
       console.warn('[persistent-cache] Desktop read failed; falling back to browser storage', error);
     }
@@ -101,9 +207,14 @@ export async function setPersistentCache<T>(key: string, data: T): Promise<void>
 
   if (isDesktopRuntime()) {
     try {
-      await invokeTauri<void>('write_cache_entry', { key, value: JSON.stringify(payload) });
+      await writeDesktopEntry(key, JSON.stringify(payload));
       return;
     } catch (error) {
+      // Refused by cache policy: browser storage would only hide the bug.
+      if (error instanceof CacheWriteRefusedError) {
+        console.warn(`[persistent-cache] Desktop cache refused '${key}': ${error.message}`);
+        return;
+      }
       console.warn('[persistent-cache] Desktop write failed; falling back to browser storage', error);
     }
   }
@@ -129,8 +240,15 @@ export async function setPersistentCache<T>(key: string, data: T): Promise<void>
 
 export async function deletePersistentCache(key: string): Promise<void> {
   if (isDesktopRuntime()) {
+    // A write still queued for this key would land after the delete and
+    // bring the entry back; the delete supersedes it.
+    const queued = pendingWrites.get(key);
+    if (queued) {
+      pendingWrites.delete(key);
+      queued.waiters.forEach(w => w.resolve());
+    }
     try {
-      await invokeTauri<void>('delete_cache_entry', { key });
+      await invokeTauri<void>('delete_cache_entry', { namespace: DESKTOP_NAMESPACE, key });
       return;
     } catch {
       // Fall through to browser storage
@@ -161,6 +279,61 @@ export async function deletePersistentCache(key: string): Promise<void> {
   }
 }
 
+/**
+ * Earlier versions of a desktop cache entry with `updatedAt >= since`, oldest
+ * first. Only keys covered by a history rule in the desktop cache policy
+ * (e.g. `risk-scores:`) keep versions; empty otherwise and outside desktop.
+ */
+export async function getPersistentCacheHistory<T>(key: string, since?: number): Promise<Array<CacheEnvelope<T>>> {
+  if (!isDesktopRuntime()) return [];
+  try {
+    const versions = await invokeTauri<Array<{ updatedAt: number; value: CacheEnvelope<T> }>>(
+      'read_cache_history',
+      { namespace: DESKTOP_NAMESPACE, key, since: since ?? null },
+    );
+    return versions.map(version => version.value);
+  } catch (error) {
+    console.warn('[persistent-cache] History read failed', error);
+    return [];
+  }
+}
+
+export type PersistentCacheChange = {
+  namespace: string;
+  key: string;
+  updatedAt: number | null;
+  deleted: boolean;
+};
+
+/**
+ * Notified whenever any trusted desktop window writes or deletes a cache
+ * entry in this variant's namespace (including this window). No-op outside
+ * the desktop runtime.
+ */
+export async function onPersistentCacheChange(
+  handler: (change: PersistentCacheChange) => void,
+): Promise<() => void> {
+  if (!isDesktopRuntime()) return () => {};
+  try {
+    return await listenTauri<PersistentCacheChange>('cache-entry-changed', (change) => {
+      if (change.namespace === DESKTOP_NAMESPACE) handler(change);
+    });
+  } catch (error) {
+    console.warn('[persistent-cache] Change events unavailable', error);
+    return () => {};
+  }
+}
+
+/**
+ * Copy desktop cache entries whose key starts with `prefix` from one
+ * variant's namespace to another; newer entries in the target are kept.
+ */
+export async function copyPersistentCacheEntries(from: string, to: string, prefix: string): Promise<number> {
+  if (!isDesktopRuntime()) return 0;
+  const report = await invokeTauri<{ imported: number }>('copy_cache_entries', { from, to, prefix });
+  return report.imported;
+}
+
 export function cacheAgeMs(updatedAt: number): number {
   return Math.max(0, Date.now() - updatedAt);
 }
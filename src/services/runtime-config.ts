@@ -1,4 +1,4 @@
-import { isDesktopRuntime } from './runtime';
+import { getLocalApiBaseUrl, isDesktopRuntime, resolveLocalApiPort } from './runtime';
 import { invokeTauri } from './tauri-bridge';
 
 export type RuntimeSecretKey =
@@ -53,8 +53,15 @@ export interface RuntimeFeatureDefinition {
 }
 
 export interface RuntimeSecretState {
-  value: string;
+  /**
+   * Env-seeded values, and vault values of non-credential keys (URLs, model
+   * names). Vault credentials stay in Rust and only expose `lastFour`.
+   */
+  value?: string;
   source: 'env' | 'vault';
+  lastFour?: string;
+  /** Format check done in Rust for vault entries. */
+  valid?: boolean;
 }
 
 export interface RuntimeConfig {
@@ -63,8 +70,7 @@ export interface RuntimeConfig {
 }
 
 const TOGGLES_STORAGE_KEY = 'worldmonitor-runtime-feature-toggles';
-const SIDECAR_ENV_UPDATE_URL = 'http://127.0.0.1:46123/api/local-env-update';
-const SIDECAR_SECRET_VALIDATE_URL = 'http://127.0.0.1:46123/api/local-validate-secret';
+const SIDECAR_SECRET_VALIDATE_PATH = '/api/local-validate-secret';
 
 const defaultToggles: Record<RuntimeFeatureId, boolean> = {
   aiGroq: true,
@@ -323,10 +329,11 @@ export function isFeatureEnabled(featureId: RuntimeFeatureId): boolean {
   return runtimeConfig.featureToggles[featureId] !== false;
 }
 
-export function getSecretState(key: RuntimeSecretKey): { present: boolean; valid: boolean; source: 'env' | 'vault' | 'missing' } {
+export function getSecretState(key: RuntimeSecretKey): { present: boolean; valid: boolean; source: 'env' | 'vault' | 'missing'; lastFour?: string } {
   const state = runtimeConfig.secrets[key];
   if (!state) return { present: false, valid: false, source: 'missing' };
-  return { present: true, valid: validateSecret(key, state.value).valid, source: state.source };
+  const valid = state.value !== undefined ? validateSecret(key, state.value).valid : state.valid !== false;
+  return { present: true, valid, source: state.source, lastFour: state.lastFour };
 }
 
 export function isFeatureAvailable(featureId: RuntimeFeatureId): boolean {
@@ -354,27 +361,34 @@ export function setFeatureToggle(featureId: RuntimeFeatureId, enabled: boolean):
   notifyConfigChanged();
 }
 
-export async function setSecretValue(key: RuntimeSecretKey, value: string): Promise<void> {
+/**
+ * How the running sidecar picked up a secret change. `set_secret` and
+ * `delete_secret` only resolve once this is known; anything but `failed`
+ * means the new value is what the sidecar uses.
+ */
+export type SidecarSyncStatus = 'pushed' | 'restarted' | 'notRunning' | 'failed';
+
+type SidecarSyncReport = { sidecar: SidecarSyncStatus; error?: string };
+
+export async function setSecretValue(key: RuntimeSecretKey, value: string): Promise<SidecarSyncStatus | null> {
   if (!isDesktopRuntime()) {
     console.warn('[runtime-config] Ignoring secret write outside desktop runtime');
-    return;
+    return null;
   }
 
   const sanitized = value.trim();
+  let sync: SidecarSyncReport;
   if (sanitized) {
-    await invokeTauri<void>('set_secret', { key, value: sanitized });
-    runtimeConfig.secrets[key] = { value: sanitized, source: 'vault' };
+    sync = await invokeTauri<SidecarSyncReport>('set_secret', { key, value: sanitized });
   } else {
-    await invokeTauri<void>('delete_secret', { key });
-    delete runtimeConfig.secrets[key];
+    sync = await invokeTauri<SidecarSyncReport>('delete_secret', { key });
   }
+  // Re-read the masked view rather than keep the plaintext around.
+  await loadDesktopSecrets();
 
-  // Push to sidecar so handlers pick it up immediately.
-  // This is best-effort: keyring persistence is the source of truth.
-  try {
-    await pushSecretToSidecar(key, sanitized || '');
-  } catch (error) {
-    console.warn(`[runtime-config] Failed to sync ${key} to sidecar`, error);
+  // Saved either way; the sidecar picks the key up on its next start.
+  if (sync.sidecar === 'failed') {
+    console.warn(`[runtime-config] ${key} saved but not applied to sidecar: ${sync.error ?? 'unknown error'}`);
   }
 
   // Signal other windows (main ↔ settings) to reload secrets from keychain.
@@ -383,7 +397,18 @@ export async function setSecretValue(key: RuntimeSecretKey, value: string): Prom
     localStorage.setItem('wm-secrets-updated', String(Date.now()));
   } catch { /* localStorage may be unavailable */ }
 
-  notifyConfigChanged();
+  return sync.sidecar;
+}
+
+/**
+ * Re-read the masked view after a bulk change (vault import, unlock or
+ * profile switch) and tell the other windows to do the same.
+ */
+export async function reloadDesktopSecrets(): Promise<void> {
+  await loadDesktopSecrets();
+  try {
+    localStorage.setItem('wm-secrets-updated', String(Date.now()));
+  } catch { /* localStorage may be unavailable */ }
 }
 
 async function getLocalApiToken(): Promise<string | null> {
@@ -399,28 +424,6 @@ async function getLocalApiToken(): Promise<string | null> {
   return localApiTokenPromise;
 }
 
-async function pushSecretToSidecar(key: string, value: string): Promise<void> {
-  const headers = new Headers({ 'Content-Type': 'application/json' });
-  const token = await getLocalApiToken();
-  if (token) {
-    headers.set('Authorization', `Bearer ${token}`);
-  }
-
-  const response = await fetch(SIDECAR_ENV_UPDATE_URL, {
-    method: 'POST',
-    headers,
-    body: JSON.stringify({ key, value: value || null }),
-  });
-
-  if (!response.ok) {
-    let detail = '';
-    try {
-      detail = await response.text();
-    } catch { /* ignore non-readable body */ }
-    throw new Error(`Sidecar secret sync failed (${response.status})${detail ? `: ${detail.slice(0, 200)}` : ''}`);
-  }
-}
-
 async function callSidecarWithAuth(url: string, init: RequestInit): Promise<Response> {
   const headers = new Headers(init.headers ?? {});
   const token = await getLocalApiToken();
@@ -445,7 +448,8 @@ export async function verifySecretWithApi(
   }
 
   try {
-    const response = await callSidecarWithAuth(SIDECAR_SECRET_VALIDATE_URL, {
+    await resolveLocalApiPort();
+    const response = await callSidecarWithAuth(`${getLocalApiBaseUrl()}${SIDECAR_SECRET_VALIDATE_PATH}`, {
       method: 'POST',
       headers: { 'Content-Type': 'application/json' },
       body: JSON.stringify({ key, value: value.trim(), context }),
@@ -475,6 +479,10 @@ export async function verifySecretWithApi(
     const message = String((payload as Record<string, unknown>).message || (valid ? 'Verified' : 'Verification failed'));
     return { valid, message };
   } catch (error) {
+    // Sidecar unreachable (often because it failed to start): check natively.
+    try {
+      return await verifySecretNatively(key, value);
+    } catch { /* fall through */ }
     // Network errors reaching the sidecar should NOT block saving.
     // Only explicit 401/403 from the provider means the key is invalid.
     const message = error instanceof Error ? error.message : 'Secret validation failed';
@@ -482,28 +490,53 @@ export async function verifySecretWithApi(
   }
 }
 
+type NativeSecretValidation = {
+  status: 'ok' | 'invalid' | 'networkError' | 'rateLimited' | 'unchecked';
+  message: string;
+};
+
+async function verifySecretNatively(key: RuntimeSecretKey, value: string): Promise<SecretVerificationResult> {
+  const result = await invokeTauri<NativeSecretValidation>('validate_secret', { key, value: value.trim() });
+  switch (result.status) {
+    case 'invalid':
+      return { valid: false, message: result.message };
+    case 'ok':
+    case 'unchecked':
+      return { valid: true, message: result.message };
+    default:
+      // Rate limits and unreachable providers say nothing about the key itself.
+      return { valid: true, message: `Saved (could not verify – ${result.message})` };
+  }
+}
+
+type MaskedSecret = {
+  key: string;
+  present: boolean;
+  lastFour?: string;
+  value?: string;
+  valid: boolean;
+};
+
+/**
+ * Refresh the vault entries of the runtime config from the masked view.
+ * Plaintext credentials are never read back; Rust keeps the sidecar in sync.
+ */
 export async function loadDesktopSecrets(): Promise<void> {
   if (!isDesktopRuntime()) return;
 
   try {
     // Single batch call to read all keychain secrets at once.
     // This triggers only ONE macOS Keychain prompt instead of 18 individual ones.
-    const allSecrets = await invokeTauri<Record<string, string>>('get_all_secrets');
-
-    const syncResults = await Promise.allSettled(
-      Object.entries(allSecrets).filter(([, value]) => value && value.trim().length > 0).map(async ([key, value]) => {
-        runtimeConfig.secrets[key as RuntimeSecretKey] = { value, source: 'vault' };
-        try {
-          await pushSecretToSidecar(key as RuntimeSecretKey, value);
-        } catch (error) {
-          console.warn(`[runtime-config] Failed to sync ${key} to sidecar`, error);
-        }
-      })
-    );
-
-    const failures = syncResults.filter((r) => r.status === 'rejected');
-    if (failures.length > 0) {
-      console.warn(`[runtime-config] ${failures.length} key(s) failed to sync to sidecar`);
+    const masked = await invokeTauri<MaskedSecret[]>('get_masked_secrets');
+
+    for (const entry of masked) {
+      const key = entry.key as RuntimeSecretKey;
+      if (entry.present) {
+        runtimeConfig.secrets[key] = { value: entry.value, lastFour: entry.lastFour, valid: entry.valid, source: 'vault' };
+      } else if (runtimeConfig.secrets[key]?.source === 'vault') {
+        // Removed from the vault, e.g. by switching credential profile.
+        delete runtimeConfig.secrets[key];
+      }
     }
 
     notifyConfigChanged();
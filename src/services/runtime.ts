@@ -4,9 +4,17 @@ const DEFAULT_REMOTE_HOSTS: Record<string, string> = {
   world: 'https://worldmonitor.app',
 };
 
-const DEFAULT_LOCAL_API_BASE = 'http://127.0.0.1:46123';
+const DEFAULT_LOCAL_API_PORT = 46123;
 const FORCE_DESKTOP_RUNTIME = import.meta.env.VITE_DESKTOP_RUNTIME === '1';
 
+// The desktop shell picks a free loopback port for the sidecar (preferring
+// 46123) and reports it over IPC. Until that resolves, the default is used.
+// A restarted sidecar may come back on another port, announced by the
+// `local-api-status` event.
+let localApiPort = DEFAULT_LOCAL_API_PORT;
+let localApiPortPromise: Promise<number> | null = null;
+let localApiStatusWatched = false;
+
 function normalizeBaseUrl(baseUrl: string): string {
   return baseUrl.replace(/\/$/, '');
 }
@@ -62,6 +70,47 @@ export function isDesktopRuntime(): boolean {
   });
 }
 
+export function getLocalApiPort(): number {
+  return localApiPort;
+}
+
+export function getLocalApiBaseUrl(): string {
+  return `http://127.0.0.1:${localApiPort}`;
+}
+
+function isValidPort(port: unknown): port is number {
+  return typeof port === 'number' && Number.isInteger(port) && port > 0;
+}
+
+function watchLocalApiStatus(): void {
+  if (localApiStatusWatched) return;
+  localApiStatusWatched = true;
+  void import('@/services/tauri-bridge')
+    .then(({ listenTauri }) => listenTauri<{ port?: number }>('local-api-status', ({ port }) => {
+      localApiPortPromise = null;
+      if (isValidPort(port)) localApiPort = port;
+    }))
+    .catch(() => {
+      localApiStatusWatched = false;
+    });
+}
+
+export function resolveLocalApiPort(): Promise<number> {
+  watchLocalApiStatus();
+  if (!localApiPortPromise) {
+    localApiPortPromise = import('@/services/tauri-bridge')
+      .then(({ tryInvokeTauri }) => tryInvokeTauri<number>('get_local_api_port'))
+      .then((port) => {
+        if (isValidPort(port)) {
+          localApiPort = port;
+        }
+        return localApiPort;
+      })
+      .catch(() => localApiPort);
+  }
+  return localApiPortPromise;
+}
+
 export function getApiBaseUrl(): string {
   if (!isDesktopRuntime()) {
     return '';
@@ -72,7 +121,7 @@ export function getApiBaseUrl(): string {
     return normalizeBaseUrl(configuredBaseUrl);
   }
 
-  return DEFAULT_LOCAL_API_BASE;
+  return getLocalApiBaseUrl();
 }
 
 export function getRemoteApiBaseUrl(): string {
@@ -116,6 +165,30 @@ function isAppOriginUrl(urlStr: string): boolean {
   }
 }
 
+function isWorldMonitorHttpsUrl(urlStr: string): boolean {
+  try {
+    const u = new URL(urlStr);
+    return u.protocol === 'https:' && (u.hostname === 'worldmonitor.app' || u.hostname.endsWith('.worldmonitor.app'));
+  } catch {
+    return false;
+  }
+}
+
+async function fetchCloudApiViaRust(url: string, method: string, headers: Headers, body: string | null): Promise<Response> {
+  const { invokeTauri } = await import('@/services/tauri-bridge');
+  const forwarded: Record<string, string> = {};
+  headers.forEach((value, name) => { forwarded[name] = value; });
+  const result = await invokeTauri<{ status: number; contentType?: string; body: string }>(
+    'fetch_cloud_api',
+    { url, method, headers: forwarded, body },
+  );
+  const noBody = result.status === 204 || result.status === 304;
+  return new Response(noBody ? null : result.body, {
+    status: result.status,
+    headers: result.contentType ? { 'Content-Type': result.contentType } : undefined,
+  });
+}
+
 function getApiTargetFromRequestInput(input: RequestInfo | URL): string | null {
   if (typeof input === 'string') {
     if (input.startsWith('/')) return input;
@@ -188,12 +261,14 @@ async function fetchLocalWithStartupRetry(
 
 // ── Security threat model for the fetch patch ──────────────────────────
 // The LOCAL_API_TOKEN exists to prevent OTHER local processes from
-// accessing the sidecar on port 46123. The renderer IS the intended
+// accessing the sidecar's loopback port. The renderer IS the intended
 // client — injecting the token automatically is correct by design.
 //
 // If the renderer is compromised (XSS, supply chain), the attacker
 // already has access to strictly more powerful Tauri IPC commands
-// (get_all_secrets, set_secret, etc.) via window.__TAURI_INTERNALS__.
+// (set_secret, delete_secret, etc.) via window.__TAURI_INTERNALS__.
+// Stored credentials are not among them: IPC only returns a masked view,
+// and plaintext reveal is limited to a confirmed request from settings.
 // The fetch patch does not expand the attack surface beyond what IPC
 // already provides.
 //
@@ -213,7 +288,7 @@ export function installRuntimeFetchPatch(): void {
   }
 
   const nativeFetch = window.fetch.bind(window);
-  const localBase = getApiBaseUrl();
+  void resolveLocalApiPort();
   let localApiToken: string | null = null;
   let tokenFetchedAt = 0;
 
@@ -247,7 +322,8 @@ export function installRuntimeFetchPatch(): void {
     }
     const localInit = { ...init, headers };
 
-    const localUrl = `${localBase}${target}`;
+    await resolveLocalApiPort();
+    const localUrl = `${getApiBaseUrl()}${target}`;
     if (debug) console.log(`[fetch] intercept → ${target}`);
     let allowCloudFallback = !isLocalOnlyApiTarget(target);
 
@@ -271,12 +347,10 @@ export function installRuntimeFetchPatch(): void {
       const cloudUrl = `${getRemoteApiBaseUrl()}${target}`;
       if (debug) console.log(`[fetch] cloud fallback → ${cloudUrl}`);
       const cloudHeaders = new Headers(init?.headers);
-      if (/^\/api\/[^/]+\/v1\//.test(target)) {
-        const { getRuntimeConfigSnapshot } = await import('@/services/runtime-config');
-        const wmKeyValue = getRuntimeConfigSnapshot().secrets['WORLDMONITOR_API_KEY']?.value;
-        if (wmKeyValue) {
-          cloudHeaders.set('X-WorldMonitor-Key', wmKeyValue);
-        }
+      const body = init?.body;
+      if (/^\/api\/[^/]+\/v1\//.test(target) && isWorldMonitorHttpsUrl(cloudUrl) && (body == null || typeof body === 'string')) {
+        // Keyed endpoint: Rust sends it so the World Monitor key stays out of the webview.
+        return fetchCloudApiViaRust(cloudUrl, init?.method ?? 'GET', cloudHeaders, body ?? null);
       }
       return nativeFetch(cloudUrl, { ...init, headers: cloudHeaders });
     };
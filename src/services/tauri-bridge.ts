@@ -44,3 +44,37 @@ export async function tryInvokeTauri<T>(
     return null;
   }
 }
+
+type TauriEventHandler<T> = (event: { event: string; id: number; payload: T }) => void;
+
+/**
+ * Subscribe to a Rust-emitted event. Mirrors `@tauri-apps/api/event.listen`
+ * on top of the injected internals, since the app does not bundle the API
+ * package. Resolves to an unlisten function.
+ */
+export async function listenTauri<T>(event: string, handler: (payload: T) => void): Promise<() => void> {
+  if (typeof window === 'undefined') {
+    throw new Error('Tauri event bridge unavailable');
+  }
+
+  const internals = (window as unknown as {
+    __TAURI_INTERNALS__?: {
+      invoke?: TauriInvoke;
+      transformCallback?: <P>(callback: TauriEventHandler<P>, once?: boolean) => number;
+    };
+  }).__TAURI_INTERNALS__;
+  const invoke = internals?.invoke;
+  const transformCallback = internals?.transformCallback;
+  if (typeof invoke !== 'function' || typeof transformCallback !== 'function') {
+    throw new Error('Tauri event bridge unavailable');
+  }
+
+  const eventId = await invoke<number>('plugin:event|listen', {
+    event,
+    target: { kind: 'Any' },
+    handler: transformCallback<T>((e) => handler(e.payload)),
+  });
+  return () => {
+    void invoke('plugin:event|unlisten', { event, eventId }).catch(() => {});
+  };
+}
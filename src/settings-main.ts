@@ -2,20 +2,23 @@ import './styles/main.css';
 import './styles/settings-window.css';
 import { RuntimeConfigPanel } from '@/components/RuntimeConfigPanel';
 import { WorldMonitorTab } from '@/components/WorldMonitorTab';
-import { RUNTIME_FEATURES, loadDesktopSecrets } from '@/services/runtime-config';
-import { tryInvokeTauri } from '@/services/tauri-bridge';
+import { RUNTIME_FEATURES, loadDesktopSecrets, reloadDesktopSecrets } from '@/services/runtime-config';
+import { getLocalApiBaseUrl, resolveLocalApiPort } from '@/services/runtime';
+import { invokeTauri, tryInvokeTauri } from '@/services/tauri-bridge';
+import { describeFreshness, onPersistentCacheChange } from '@/services/persistent-cache';
+import { SITE_VARIANT } from '@/config/variant';
 import { escapeHtml } from '@/utils/sanitize';
 import { initI18n, t } from '@/services/i18n';
 import { applyStoredTheme } from '@/utils/theme-manager';
 
 let diagnosticsInitialized = false;
 
-function setActionStatus(message: string, tone: 'ok' | 'error' = 'ok'): void {
+function setActionStatus(message: string, tone: 'ok' | 'warn' | 'error' = 'ok'): void {
   const statusEl = document.getElementById('settingsActionStatus');
   if (!statusEl) return;
 
   statusEl.textContent = message;
-  statusEl.classList.remove('ok', 'error');
+  statusEl.classList.remove('ok', 'warn', 'error');
   statusEl.classList.add(tone);
 }
 
@@ -55,6 +58,7 @@ function initTabs(): void {
       if (target === 'debug' && !diagnosticsInitialized) {
         diagnosticsInitialized = true;
         initDiagnostics();
+        initStorage();
       }
     });
   });
@@ -164,10 +168,355 @@ async function initSettingsWindow(): Promise<void> {
     void invokeDesktopAction('open_sidecar_log_file', t('modals.settingsWindow.openApiLog'));
   });
 
+  initVaultUnlock();
+  initProfiles(panels);
+  initKeyStatus();
+  initVault();
+  initKeyAudit();
   initTabs();
 }
 
-const SIDECAR_BASE = 'http://127.0.0.1:46123';
+type SecretsVaultStatus = { backend: 'keyring' | 'file'; locked: boolean; exists: boolean };
+
+function initVaultUnlock(): void {
+  const section = document.getElementById('vaultUnlockSection');
+  const heading = document.getElementById('vaultUnlockHeading');
+  const hint = document.getElementById('vaultUnlockHint');
+  const passphraseInput = document.getElementById('vaultUnlockPassphrase') as HTMLInputElement | null;
+  const unlockBtn = document.getElementById('unlockVaultBtn');
+  if (!section || !passphraseInput) return;
+
+  function render(status: SecretsVaultStatus): void {
+    if (!section) return;
+    section.hidden = status.backend !== 'file' || !status.locked;
+    const mode = status.exists ? 'locked' : 'create';
+    if (heading) heading.textContent = t(`modals.settingsWindow.vaultUnlock.${mode}Title`);
+    if (hint) hint.textContent = t(`modals.settingsWindow.vaultUnlock.${mode}Hint`);
+    if (unlockBtn) unlockBtn.textContent = t(`modals.settingsWindow.vaultUnlock.${mode}`);
+  }
+
+  async function unlock(): Promise<void> {
+    const passphrase = passphraseInput?.value ?? '';
+    if (!passphrase) return;
+    try {
+      const status = await invokeTauri<SecretsVaultStatus>('unlock_secrets_vault', { passphrase });
+      if (passphraseInput) passphraseInput.value = '';
+      render(status);
+      await reloadDesktopSecrets();
+      setActionStatus(t('modals.settingsWindow.vaultUnlock.unlocked'), 'ok');
+    } catch (error) {
+      setActionStatus(t('modals.settingsWindow.vaultUnlock.failed', { error: String(error) }), 'error');
+    }
+  }
+
+  unlockBtn?.addEventListener('click', () => void unlock());
+  passphraseInput.addEventListener('keydown', (event) => {
+    if (event.key === 'Enter') void unlock();
+  });
+
+  void tryInvokeTauri<SecretsVaultStatus>('get_secrets_vault_status').then((status) => {
+    if (status) render(status);
+  });
+}
+
+type SecretProfileList = { active: string; profiles: Array<{ name: string; keyCount: number }> };
+
+function initProfiles(panels: RuntimeConfigPanel[]): void {
+  const section = document.getElementById('profileSection');
+  const select = document.getElementById('profileSelect') as HTMLSelectElement | null;
+  const nameInput = document.getElementById('newProfileName') as HTMLInputElement | null;
+  const copyToggle = document.getElementById('copyProfileKeys') as HTMLInputElement | null;
+  if (!section || !select) return;
+
+  function render(list: SecretProfileList): void {
+    if (!section || !select) return;
+    section.hidden = false;
+    select.innerHTML = list.profiles.map(p => `<option value="${escapeHtml(p.name)}">${escapeHtml(t('modals.settingsWindow.profiles.option', { name: p.name, count: p.keyCount }))}</option>`).join('');
+    select.value = list.active;
+    select.dataset.active = list.active;
+  }
+
+  select.addEventListener('change', () => {
+    const name = select.value;
+    if (panels.some(p => p.hasPendingChanges()) && !window.confirm(t('modals.settingsWindow.profiles.confirmDiscard'))) {
+      select.value = select.dataset.active ?? name;
+      return;
+    }
+    void (async () => {
+      try {
+        render(await invokeTauri<SecretProfileList>('switch_secret_profile', { name }));
+        await reloadDesktopSecrets();
+        setActionStatus(t('modals.settingsWindow.profiles.switched', { name }), 'ok');
+      } catch (error) {
+        select.value = select.dataset.active ?? name;
+        setActionStatus(t('modals.settingsWindow.profiles.failed', { error: String(error) }), 'error');
+      }
+    })();
+  });
+
+  document.getElementById('createProfileBtn')?.addEventListener('click', () => {
+    const name = nameInput?.value.trim() ?? '';
+    if (!name) {
+      nameInput?.focus();
+      return;
+    }
+    void (async () => {
+      try {
+        render(await invokeTauri<SecretProfileList>('create_secret_profile', { name, copyActive: copyToggle?.checked ?? false }));
+        if (nameInput) nameInput.value = '';
+        setActionStatus(t('modals.settingsWindow.profiles.created', { name }), 'ok');
+      } catch (error) {
+        setActionStatus(t('modals.settingsWindow.profiles.failed', { error: String(error) }), 'error');
+      }
+    })();
+  });
+
+  void tryInvokeTauri<SecretProfileList>('list_secret_profiles').then((list) => {
+    if (list) render(list);
+  });
+}
+
+type MaskedSecret = {
+  key: string;
+  present: boolean;
+  setAt?: number;
+  lastValidatedAt?: number;
+  lastResult?: 'ok' | 'invalid' | 'networkError' | 'rateLimited' | 'unchecked';
+  expiresAt?: number;
+  expired: boolean;
+  expiresSoon: boolean;
+  lastFour?: string;
+  value?: string;
+};
+
+const REVEAL_DURATION_MS = 30_000;
+
+function toDateInputValue(ms: number): string {
+  const d = new Date(ms);
+  return `${d.getFullYear()}-${String(d.getMonth() + 1).padStart(2, '0')}-${String(d.getDate()).padStart(2, '0')}`;
+}
+
+function initKeyStatus(): void {
+  const tableEl = document.getElementById('keyStatusTable');
+  if (!tableEl) return;
+
+  function render(statuses: MaskedSecret[]): void {
+    if (!tableEl) return;
+    const present = statuses.filter(s => s.present);
+    if (present.length === 0) {
+      tableEl.innerHTML = `<p class="diag-empty">${t('modals.settingsWindow.keyStatus.empty')}</p>`;
+      return;
+    }
+    const rows = present.map((s) => {
+      const tone = s.expired || s.lastResult === 'invalid' ? 'diag-err' : s.expiresSoon ? 'diag-warn' : 'diag-ok';
+      const setAt = s.setAt ? describeFreshness(s.setAt) : t('modals.settingsWindow.keyStatus.unknown');
+      const checked = s.lastValidatedAt && s.lastResult
+        ? `${t(`modals.settingsWindow.keyStatus.${s.lastResult}`)} · ${describeFreshness(s.lastValidatedAt)}`
+        : t('modals.settingsWindow.keyStatus.never');
+      const expiry = s.expiresAt ? toDateInputValue(s.expiresAt) : '';
+      const key = escapeHtml(s.key);
+      const shown = s.value ?? `••••${s.lastFour ?? ''}`;
+      const reveal = s.value === undefined
+        ? ` <button type="button" data-reveal="${key}">${t('modals.settingsWindow.keyStatus.reveal')}</button>`
+        : '';
+      return `<tr class="${tone}"><td>${key}</td><td><span data-value="${key}">${escapeHtml(shown)}</span>${reveal}</td><td>${escapeHtml(setAt)}</td><td>${escapeHtml(checked)}</td><td><input type="date" data-key="${key}" value="${expiry}" aria-label="${escapeHtml(t('modals.settingsWindow.keyStatus.expires'))}"></td><td><button type="button" data-key="${key}">${t('modals.settingsWindow.keyStatus.check')}</button></td></tr>`;
+    }).join('');
+    tableEl.innerHTML = `<table class="diag-table"><thead><tr><th>${t('modals.settingsWindow.keyStatus.key')}</th><th>${t('modals.settingsWindow.keyStatus.value')}</th><th>${t('modals.settingsWindow.keyStatus.set')}</th><th>${t('modals.settingsWindow.keyStatus.lastCheck')}</th><th>${t('modals.settingsWindow.keyStatus.expires')}</th><th></th></tr></thead><tbody>${rows}</tbody></table>`;
+  }
+
+  async function refresh(): Promise<void> {
+    const statuses = await tryInvokeTauri<MaskedSecret[]>('get_masked_secrets');
+    if (statuses) render(statuses);
+  }
+
+  tableEl.addEventListener('change', (event) => {
+    const input = (event.target as HTMLElement).closest<HTMLInputElement>('input[type="date"]');
+    const key = input?.dataset.key;
+    if (!input || !key) return;
+    const expiresAt = input.value ? new Date(`${input.value}T00:00:00`).getTime() : null;
+    void (async () => {
+      try {
+        await invokeTauri<unknown>('set_secret_expiry', { key, expiresAt });
+        await refresh();
+      } catch (error) {
+        setActionStatus(t('modals.settingsWindow.keyStatus.failed', { error: String(error) }), 'error');
+      }
+    })();
+  });
+
+  tableEl.addEventListener('click', (event) => {
+    const revealBtn = (event.target as HTMLElement).closest<HTMLButtonElement>('button[data-reveal]');
+    const revealKey = revealBtn?.dataset.reveal;
+    if (revealBtn && revealKey) {
+      void (async () => {
+        try {
+          const value = await invokeTauri<string>('reveal_secret', { key: revealKey });
+          const cell = tableEl.querySelector<HTMLElement>(`span[data-value="${CSS.escape(revealKey)}"]`);
+          if (cell) cell.textContent = value;
+          revealBtn.hidden = true;
+          // Mask it again after a short while.
+          setTimeout(() => void refresh(), REVEAL_DURATION_MS);
+        } catch (error) {
+          setActionStatus(t('modals.settingsWindow.keyStatus.failed', { error: String(error) }), 'error');
+        }
+      })();
+      return;
+    }
+    const button = (event.target as HTMLElement).closest<HTMLButtonElement>('button[data-key]');
+    const key = button?.dataset.key;
+    if (!button || !key) return;
+    button.disabled = true;
+    void (async () => {
+      try {
+        const result = await invokeTauri<{ status: string; message: string }>('validate_secret', { key });
+        const tone = result.status === 'ok' || result.status === 'unchecked' ? 'ok'
+          : result.status === 'invalid' ? 'error' : 'warn';
+        setActionStatus(`${key}: ${result.message}`, tone);
+        await refresh();
+      } catch (error) {
+        button.disabled = false;
+        setActionStatus(t('modals.settingsWindow.keyStatus.failed', { error: String(error) }), 'error');
+      }
+    })();
+  });
+
+  document.getElementById('refreshKeyStatusBtn')?.addEventListener('click', () => void refresh());
+  void refresh();
+}
+
+type SecretAuditEntry = {
+  timestamp: number;
+  key?: string;
+  profile?: string;
+  operation: 'set' | 'delete' | 'import' | 'setExpiry' | 'reveal' | 'unlock' | 'createProfile' | 'switchProfile';
+  window: string;
+  result: 'ok' | 'failed';
+  error?: string;
+};
+
+function initKeyAudit(): void {
+  const tableEl = document.getElementById('keyAuditTable');
+  if (!tableEl) return;
+
+  async function refresh(): Promise<void> {
+    if (!tableEl) return;
+    const entries = await tryInvokeTauri<SecretAuditEntry[]>('get_secret_audit_log', { limit: 200 });
+    if (!entries) return;
+    if (entries.length === 0) {
+      tableEl.innerHTML = `<p class="diag-empty">${t('modals.settingsWindow.keyAudit.empty')}</p>`;
+      return;
+    }
+    const rows = entries.map((e) => {
+      const result = e.result === 'ok'
+        ? t('modals.settingsWindow.keyAudit.ok')
+        : e.error ?? t('modals.settingsWindow.keyAudit.failed');
+      const subject = e.key ?? (e.profile ? t('modals.settingsWindow.keyAudit.profile', { name: e.profile }) : '');
+      return `<tr class="${e.result === 'ok' ? 'diag-ok' : 'diag-err'}"><td>${escapeHtml(new Date(e.timestamp).toLocaleString())}</td><td>${escapeHtml(subject)}</td><td>${t(`modals.settingsWindow.keyAudit.${e.operation}`)}</td><td>${escapeHtml(e.window)}</td><td>${escapeHtml(result)}</td></tr>`;
+    }).join('');
+    tableEl.innerHTML = `<table class="diag-table"><thead><tr><th>${t('modals.settingsWindow.keyAudit.time')}</th><th>${t('modals.settingsWindow.keyAudit.key')}</th><th>${t('modals.settingsWindow.keyAudit.operation')}</th><th>${t('modals.settingsWindow.keyAudit.window')}</th><th>${t('modals.settingsWindow.keyAudit.result')}</th></tr></thead><tbody>${rows}</tbody></table>`;
+  }
+
+  document.getElementById('refreshKeyAuditBtn')?.addEventListener('click', () => void refresh());
+  void refresh();
+}
+
+type VaultMergeStatus = 'added' | 'changed' | 'unchanged' | 'unsupported';
+
+function initVault(): void {
+  const passphraseInput = document.getElementById('vaultPassphrase') as HTMLInputElement | null;
+  const previewEl = document.getElementById('vaultPreview');
+  // The picked file and its key stay in the desktop shell; this only tracks
+  // whether a preview is on screen.
+  let pendingImport = false;
+
+  function readPassphrase(): string | null {
+    const passphrase = passphraseInput?.value ?? '';
+    if (passphrase.length >= 12) return passphrase;
+    setActionStatus(t('modals.settingsWindow.vault.passphraseRequired'), 'error');
+    passphraseInput?.focus();
+    return null;
+  }
+
+  function clearPreview(): void {
+    if (pendingImport) void tryInvokeTauri<void>('cancel_secrets_import');
+    pendingImport = false;
+    if (previewEl) previewEl.innerHTML = '';
+  }
+
+  document.getElementById('exportVaultBtn')?.addEventListener('click', () => {
+    const passphrase = readPassphrase();
+    if (!passphrase) return;
+    void (async () => {
+      try {
+        const path = await invokeTauri<string>('export_secrets_vault', { passphrase });
+        setActionStatus(t('modals.settingsWindow.vault.exported', { path }), 'ok');
+      } catch (error) {
+        setActionStatus(t('modals.settingsWindow.vault.exportFailed', { error: String(error) }), 'error');
+      }
+    })();
+  });
+
+  document.getElementById('importVaultBtn')?.addEventListener('click', () => {
+    const passphrase = readPassphrase();
+    if (!passphrase || !previewEl) return;
+    void (async () => {
+      clearPreview();
+      try {
+        // The desktop shell shows the file picker and reads the file itself.
+        const items = await invokeTauri<Array<{ key: string; status: VaultMergeStatus }> | null>(
+          'preview_secrets_import',
+          { passphrase },
+        );
+        if (!items) return;
+        pendingImport = true;
+        if (!items.some(item => item.status === 'added' || item.status === 'changed')) {
+          clearPreview();
+          setActionStatus(t('modals.settingsWindow.vault.nothingToImport'), 'ok');
+          return;
+        }
+        const rows = items.map((item) => {
+          const selectable = item.status === 'added' || item.status === 'changed';
+          return `<tr><td><input type="checkbox" data-key="${escapeHtml(item.key)}"${selectable ? ' checked' : ' disabled'}></td><td>${escapeHtml(item.key)}</td><td>${t(`modals.settingsWindow.vault.${item.status}`)}</td></tr>`;
+        }).join('');
+        previewEl.innerHTML = `<table class="diag-table"><thead><tr><th></th><th>${t('modals.settingsWindow.vault.key')}</th><th>${t('modals.settingsWindow.vault.status')}</th></tr></thead><tbody>${rows}</tbody></table><div class="diag-traffic-controls"><button id="applyVaultImportBtn" type="button">${t('modals.settingsWindow.vault.apply')}</button><button id="cancelVaultImportBtn" type="button">${t('modals.settingsWindow.vault.cancel')}</button></div>`;
+      } catch (error) {
+        setActionStatus(t('modals.settingsWindow.vault.previewFailed', { error: String(error) }), 'error');
+      }
+    })();
+  });
+
+  previewEl?.addEventListener('click', (event) => {
+    const button = (event.target as HTMLElement).closest<HTMLButtonElement>('button');
+    if (!button) return;
+    if (button.id === 'cancelVaultImportBtn') {
+      clearPreview();
+      return;
+    }
+    if (button.id !== 'applyVaultImportBtn' || !pendingImport) return;
+    const keys = Array.from(previewEl.querySelectorAll<HTMLInputElement>('input[data-key]:checked:not(:disabled)'))
+      .map(input => input.dataset.key ?? '');
+    // The shell consumes the previewed file whether or not the import succeeds.
+    pendingImport = false;
+    void (async () => {
+      try {
+        const count = await invokeTauri<number>('import_secrets_vault', { keys });
+        clearPreview();
+        if (passphraseInput) passphraseInput.value = '';
+        // Re-read the vault so the panels, the sidecar and the main window see the imported keys.
+        await reloadDesktopSecrets();
+        setActionStatus(t('modals.settingsWindow.vault.imported', { count }), 'ok');
+      } catch (error) {
+        clearPreview();
+        setActionStatus(t('modals.settingsWindow.vault.importFailed', { error: String(error) }), 'error');
+      }
+    })();
+  });
+}
+
+async function sidecarUrl(path: string): Promise<string> {
+  await resolveLocalApiPort();
+  return `${getLocalApiBaseUrl()}${path}`;
+}
 
 function initDiagnostics(): void {
   const verboseToggle = document.getElementById('verboseApiLog') as HTMLInputElement | null;
@@ -188,7 +537,7 @@ function initDiagnostics(): void {
   async function syncVerboseState(): Promise<void> {
     if (!verboseToggle) return;
     try {
-      const res = await fetch(`${SIDECAR_BASE}/api/local-debug-toggle`);
+      const res = await fetch(await sidecarUrl('/api/local-debug-toggle'));
       const data = await res.json();
       verboseToggle.checked = data.verboseMode;
     } catch { /* sidecar not running */ }
@@ -196,7 +545,7 @@ function initDiagnostics(): void {
 
   verboseToggle?.addEventListener('change', async () => {
     try {
-      const res = await fetch(`${SIDECAR_BASE}/api/local-debug-toggle`, { method: 'POST' });
+      const res = await fetch(await sidecarUrl('/api/local-debug-toggle'), { method: 'POST' });
       const data = await res.json();
       if (verboseToggle) verboseToggle.checked = data.verboseMode;
       setActionStatus(data.verboseMode ? t('modals.settingsWindow.verboseOn') : t('modals.settingsWindow.verboseOff'), 'ok');
@@ -210,7 +559,7 @@ function initDiagnostics(): void {
   async function refreshTrafficLog(): Promise<void> {
     if (!trafficLogEl) return;
     try {
-      const res = await fetch(`${SIDECAR_BASE}/api/local-traffic-log`);
+      const res = await fetch(await sidecarUrl('/api/local-traffic-log'));
       const data = await res.json();
       const entries: Array<{ timestamp: string; method: string; path: string; status: number; durationMs: number }> = data.entries || [];
       if (trafficCount) trafficCount.textContent = `(${entries.length})`;
@@ -236,7 +585,7 @@ function initDiagnostics(): void {
 
   clearBtn?.addEventListener('click', async () => {
     try {
-      await fetch(`${SIDECAR_BASE}/api/local-traffic-log`, { method: 'DELETE' });
+      await fetch(await sidecarUrl('/api/local-traffic-log'), { method: 'DELETE' });
     } catch { /* ignore */ }
     if (trafficLogEl) trafficLogEl.innerHTML = `<p class="diag-empty">${t('modals.settingsWindow.logCleared')}</p>`;
     if (trafficCount) trafficCount.textContent = '(0)';
@@ -261,6 +610,148 @@ function initDiagnostics(): void {
   startAutoRefresh();
 }
 
+interface CacheEntryInfo { key: string; sizeBytes: number; updatedAt: number }
+interface CacheStats { entryCount: number; totalBytes: number; maxBytes: number; lastFlushMs: number; maxFlushMs: number; refusedWrites: number }
+interface CacheImportResult {
+  manifest: { appVersion: string; variant: string; keyCount: number };
+  imported: number;
+  keptNewer: number;
+  removed: number;
+  refused: Array<{ key: string; code: string; message: string }>;
+}
+interface CacheGroup { prefix: string; count: number; bytes: number; updatedAt: number }
+
+function formatBytes(bytes: number): string {
+  if (bytes < 1024) return `${bytes} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
+
+/** Group keys by their namespace (`feed:`, `api-response:`, ...). */
+function groupCacheEntries(entries: CacheEntryInfo[]): CacheGroup[] {
+  const groups = new Map<string, CacheGroup>();
+  for (const entry of entries) {
+    const sep = entry.key.indexOf(':');
+    const prefix = sep >= 0 ? entry.key.slice(0, sep + 1) : entry.key;
+    const group = groups.get(prefix) ?? { prefix, count: 0, bytes: 0, updatedAt: 0 };
+    group.count += 1;
+    group.bytes += entry.sizeBytes;
+    group.updatedAt = Math.max(group.updatedAt, entry.updatedAt);
+    groups.set(prefix, group);
+  }
+  return [...groups.values()].sort((a, b) => b.bytes - a.bytes);
+}
+
+function initStorage(): void {
+  const heading = document.getElementById('storageHeading');
+  const listEl = document.getElementById('storageList');
+  const refreshBtn = document.getElementById('refreshStorageBtn');
+
+  async function refreshStorage(): Promise<void> {
+    if (!listEl) return;
+    const [stats, entries] = await Promise.all([
+      tryInvokeTauri<CacheStats>('get_cache_stats', { namespace: SITE_VARIANT }),
+      tryInvokeTauri<CacheEntryInfo[]>('list_cache_entries', { namespace: SITE_VARIANT }),
+    ]);
+    if (!stats || !entries) {
+      if (heading) heading.textContent = t('modals.settingsWindow.storage.title');
+      listEl.innerHTML = `<p class="diag-empty">${t('modals.settingsWindow.storage.unavailable')}</p>`;
+      return;
+    }
+
+    if (heading) {
+      const summary = t('modals.settingsWindow.storage.summary', {
+        count: stats.entryCount,
+        size: formatBytes(stats.totalBytes),
+        quota: formatBytes(stats.maxBytes),
+        lastFlush: stats.lastFlushMs,
+        maxFlush: stats.maxFlushMs,
+        refused: stats.refusedWrites,
+      });
+      heading.innerHTML = `${t('modals.settingsWindow.storage.title')} <span>${escapeHtml(summary)}</span>`;
+    }
+
+    const groups = groupCacheEntries(entries);
+    if (groups.length === 0) {
+      listEl.innerHTML = `<p class="diag-empty">${t('modals.settingsWindow.storage.empty')}</p>`;
+      return;
+    }
+
+    const rows = groups.map((g) => {
+      const updated = g.updatedAt ? new Date(g.updatedAt).toLocaleString() : '';
+      return `<tr><td title="${escapeHtml(g.prefix)}">${escapeHtml(g.prefix)}</td><td>${g.count}</td><td>${formatBytes(g.bytes)}</td><td>${escapeHtml(updated)}</td><td><button type="button" data-prefix="${escapeHtml(g.prefix)}">${t('modals.settingsWindow.storage.clear')}</button></td></tr>`;
+    }).join('');
+
+    listEl.innerHTML = `<table class="diag-table"><thead><tr><th>${t('modals.settingsWindow.storage.group')}</th><th>${t('modals.settingsWindow.storage.entries')}</th><th>${t('modals.settingsWindow.storage.size')}</th><th>${t('modals.settingsWindow.storage.lastUpdated')}</th><th></th></tr></thead><tbody>${rows}</tbody></table>`;
+  }
+
+  listEl?.addEventListener('click', (event) => {
+    const button = (event.target as HTMLElement).closest<HTMLButtonElement>('button[data-prefix]');
+    const prefix = button?.dataset.prefix;
+    if (!prefix) return;
+    void (async () => {
+      const removed = await tryInvokeTauri<number>('delete_cache_entries_by_prefix', { namespace: SITE_VARIANT, prefix });
+      if (removed === null) {
+        setActionStatus(t('modals.settingsWindow.invokeFail', { command: 'delete_cache_entries_by_prefix' }), 'error');
+        return;
+      }
+      setActionStatus(t('modals.settingsWindow.storage.cleared', { count: removed, prefix }), 'ok');
+      await refreshStorage();
+    })();
+  });
+
+  refreshBtn?.addEventListener('click', () => void refreshStorage());
+
+  document.getElementById('exportStorageBtn')?.addEventListener('click', () => {
+    void (async () => {
+      const path = await tryInvokeTauri<string>('export_cache_snapshot', { namespace: SITE_VARIANT });
+      if (path) setActionStatus(t('modals.settingsWindow.storage.exported', { path }), 'ok');
+      else setActionStatus(t('modals.settingsWindow.invokeFail', { command: 'export_cache_snapshot' }), 'error');
+    })();
+  });
+
+  const importSnapshot = (mode: 'merge' | 'replace'): void => {
+    void (async () => {
+      try {
+        const result = await invokeTauri<CacheImportResult | null>('import_cache_snapshot', {
+          namespace: SITE_VARIANT,
+          mode,
+        });
+        if (!result) return;
+        setActionStatus(t('modals.settingsWindow.storage.imported', {
+          imported: result.imported,
+          variant: result.manifest.variant,
+          version: result.manifest.appVersion,
+          keptNewer: result.keptNewer,
+          removed: result.removed,
+          refused: result.refused.length,
+        }), 'ok');
+        await refreshStorage();
+      } catch (error) {
+        setActionStatus(t('modals.settingsWindow.storage.importFailed', { error: String(error) }), 'error');
+      }
+    })();
+  };
+  document.getElementById('importStorageBtn')?.addEventListener('click', () => importSnapshot('merge'));
+  document.getElementById('replaceStorageBtn')?.addEventListener('click', () => {
+    if (!window.confirm(t('modals.settingsWindow.storage.confirmReplace'))) return;
+    importSnapshot('replace');
+  });
+
+  // Other windows write the cache constantly; coalesce their change events
+  // into one refresh per second.
+  let changeRefresh: ReturnType<typeof setTimeout> | null = null;
+  void onPersistentCacheChange(() => {
+    if (changeRefresh) return;
+    changeRefresh = setTimeout(() => {
+      changeRefresh = null;
+      void refreshStorage();
+    }, 1000);
+  }).then((unlisten) => window.addEventListener('beforeunload', unlisten));
+
+  void refreshStorage();
+}
+
 // Signal main window that settings is open (suppresses alert popups)
 localStorage.setItem('wm-settings-open', '1');
 window.addEventListener('beforeunload', () => localStorage.removeItem('wm-settings-open'));